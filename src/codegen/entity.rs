@@ -10,7 +10,7 @@ use sqlx::pool::PoolConnection;
 use sqlx::{AnyConnection, AnyPool, Error};
 use sqlx_mysql::MySql;
 use crate::codegen::utils;
-use crate::codegen::utils::{format_name, TableRow};
+use crate::codegen::utils::{format_name, SchemaSource, TableRow};
 use crate::utils::stringUtils;
 use serde::{Serialize, Deserialize};
 use crate::mapping::description::{RustDataType, SqlColumn};
@@ -73,7 +73,7 @@ impl EntityGenerateConfig{
 
 
 //generate entities according to db & table definitions
-pub async fn generate_entities(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, db_name:&str, config:EntityGenerateConfig){/*entity_out_dir:&str, boolean_columns: &HashMap<String, HashSet<String>>, trait_for_enum_types: &HashMap<&str, &str>*/
+pub async fn generate_entities<S: SchemaSource>(conn: &S, db_name:&str, config:EntityGenerateConfig){/*entity_out_dir:&str, boolean_columns: &HashMap<String, HashSet<String>>, trait_for_enum_types: &HashMap<&str, &str>*/
     let entity_out_dir = config.output_dir.clone();
     let boolean_columns = config.boolean_columns.clone();
     let mut trait_for_enum_types = config.trait_for_enum_types.clone();
@@ -83,7 +83,7 @@ pub async fn generate_entities(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, db_n
     let entity_out_path = std::path::Path::new(&entity_out_dir);
     utils::prepare_directory(entity_out_path);
 
-    let tables = utils::get_tables(conn).await;
+    let tables = conn.get_tables().await;
     //println!("{:#?}",tables);
 
     //collect what has been generated
@@ -129,6 +129,7 @@ pub async fn generate_entities(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, db_n
     writeln!(entity_mod_out_file_buf_writer,"#[allow(non_snake_case)]").expect("Failed to write entity/mod.rs");
     writeln!(entity_mod_out_file_buf_writer,"pub mod enums;").expect("Failed to write entity/mod.rs");
 
+    let struct_names = generated_entities.iter().map(|it| stringUtils::begin_with_upper_case(&it.struct_name)).collect::<Vec<String>>();
     for generated_entity_info in generated_entities {
         writeln!(entity_mod_out_file_buf_writer,"pub mod {};",&generated_entity_info.file_name_without_ext).expect("Failed to write entity/mod.rs");
         writeln!(entity_mod_out_file_buf_writer,"pub use {}::{};",&generated_entity_info.file_name_without_ext,stringUtils::begin_with_upper_case(&generated_entity_info.struct_name)).expect("Failed to write entity/mod.rs");
@@ -140,6 +141,14 @@ pub async fn generate_entities(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, db_n
         }
     }
 
+    //lets fetch/fetch_one decode the entities with their RowMappable impl
+    writeln!(entity_mod_out_file_buf_writer,"\n///makes fetch/fetch_one decode the entities with RowMappable instead of going through json, call it once at startup").expect("Failed to write entity/mod.rs");
+    writeln!(entity_mod_out_file_buf_writer,"pub fn register_row_mappables() {{").expect("Failed to write entity/mod.rs");
+    for struct_name in struct_names {
+        writeln!(entity_mod_out_file_buf_writer,"    rustnq::query::builder::register_row_mappable::<{}>();",struct_name).expect("Failed to write entity/mod.rs");
+    }
+    writeln!(entity_mod_out_file_buf_writer,"}}").expect("Failed to write entity/mod.rs");
+
     //define traits for enums
     let mut added_enum_traits:Vec<String> = vec![];
     for (pattern, enum_trait) in trait_for_enum_types{
@@ -155,10 +164,10 @@ pub async fn generate_entities(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, db_n
     entity_enum_mod_out_file_buf_writer.flush().expect("Failed to flush buffer");
 }
 
-async fn generate_entity(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, table: TableRow, output_path:&Path,
+async fn generate_entity<S: SchemaSource>(conn: &S, table: TableRow, output_path:&Path,
                          boolean_columns: &HashMap<String, Vec<String>>, trait_for_enum_types: &HashMap<String, String>, field_naming_convention: NamingConvention) -> GeneratedStructInfo{
     let struct_name = stringUtils::begin_with_upper_case(&format_name(&table.name, NamingConvention::CamelCase));
-    let fields_result = utils::get_table_fields(conn, &table.name).await;
+    let fields_result = conn.get_table_fields(&table.name).await;
     let file_name_without_ext = format_name(&table.name, NamingConvention::CamelCase);
     let out_file = output_path.join(format!("{}.rs", &file_name_without_ext));
 
@@ -173,9 +182,10 @@ async fn generate_entity(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, table: Tab
 
     let mut buf_writer = BufWriter::new(file);
 
-    let mut items_to_be_imported = vec!["serde::Deserialize".to_string(), "serde::Serialize".to_string()];
+    let mut items_to_be_imported = vec!["serde::Deserialize".to_string(), "serde::Serialize".to_string(), "rustnq::query::builder::{RowMappable, get_column}".to_string()];
     let mut struct_fields = vec![];
     let mut struct_fields_init = vec![];
+    let mut struct_fields_from_row = vec![];
     let mut enum_file_names_without_ext = vec![];
     //let mut primary_key = String::new();
 
@@ -204,11 +214,17 @@ async fn generate_entity(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, table: Tab
                     }
                 }
 
+                let row_decode_expression = resolve_row_decode_expression(&table.name, &it.name, &field_definition);
+                if row_decode_expression.starts_with("get_parsed_column") && !items_to_be_imported.contains(&"rustnq::query::builder::get_parsed_column".to_string()) {
+                    items_to_be_imported.push("rustnq::query::builder::get_parsed_column".to_string());
+                }
+
                 //here we need to modify the field name if it matches one of the rust keyword
                 if utils::reserved_field_names().contains(&it.name){
                     let mut struct_field_definition = format!("#[serde(rename = \"{}\")] pub {}_:{},",&it.name, &it.name,field_type_qualified_name);
                     struct_fields.push(struct_field_definition);
                     struct_fields_init.push(format!("{}_:{},",&it.name, if field_type_qualified_name.starts_with("Vec") {"vec![]"} else {"None"}));
+                    struct_fields_from_row.push(format!("{}_:{},",&it.name, row_decode_expression));
                 }else {
 
                     if field_type_qualified_name.clone().contains("DateTime<Local>"){
@@ -230,6 +246,7 @@ async fn generate_entity(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, table: Tab
                     let mut struct_field_definition = format!("pub {}:{},", format_name(&it.name, field_naming_convention), field_type_qualified_name);
                     struct_fields.push(struct_field_definition);
                     struct_fields_init.push(format!("{}:{},", format_name(&it.name, field_naming_convention), "None"));
+                    struct_fields_from_row.push(format!("{}:{},", format_name(&it.name, field_naming_convention), row_decode_expression));
                 }
             }
         }
@@ -266,6 +283,18 @@ async fn generate_entity(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, table: Tab
     writeln!(buf_writer,"    }}").expect("Failed to write entity impl code");
     writeln!(buf_writer,"}}").expect("Failed to write entity impl code");
 
+    //direct row decoding used by fetch_mapped/fetch_one_mapped
+    writeln!(buf_writer,"impl<D> RowMappable for {}<D> {{", struct_name).expect("Failed to write RowMappable impl code");
+    writeln!(buf_writer,"    fn from_row(row: &sqlx_mysql::MySqlRow) -> Result<Self, sqlx::Error> {{").expect("Failed to write RowMappable impl code");
+    writeln!(buf_writer,"        Ok({} {{", struct_name).expect("Failed to write RowMappable impl code");
+    for field in struct_fields_from_row{
+        writeln!(buf_writer,"            {}",field).expect("Failed to write RowMappable impl code");
+    }
+    writeln!(buf_writer,"            _associated: None,").expect("Failed to write RowMappable impl code");
+    writeln!(buf_writer,"        }})").expect("Failed to write RowMappable impl code");
+    writeln!(buf_writer,"    }}").expect("Failed to write RowMappable impl code");
+    writeln!(buf_writer,"}}").expect("Failed to write RowMappable impl code");
+
     drop(buf_writer);
 
     GeneratedStructInfo{
@@ -275,8 +304,8 @@ async fn generate_entity(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, table: Tab
     }
 }
 
-pub async fn generate_page_list(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, table_name: &str, field_naming_convention: NamingConvention) -> Option<HashMap<String, HashMap::<String, String>>> {
-    let fields_result = utils::get_table_full_fields(conn, table_name).await;
+pub async fn generate_page_list<S: SchemaSource>(conn: &S, table_name: &str, field_naming_convention: NamingConvention) -> Option<HashMap<String, HashMap::<String, String>>> {
+    let fields_result = conn.get_table_full_fields(table_name).await;
 
     match fields_result {
         Ok(fields) => {
@@ -559,6 +588,17 @@ fn resolve_type_from_column_definition(table_name: &str, column_name: &str, colu
     }
 }
 
+//expression decoding the column in the generated RowMappable impl, it has to agree with resolve_type_from_column_definition
+fn resolve_row_decode_expression(table_name: &str, column_name: &str, column_definition: &str) -> String {
+    let data_type = column_definition.split('(').next().unwrap_or("");
+    match data_type.parse::<SqlColumn>() {
+        Ok(SqlColumn::Decimal(_)) => format!("get_parsed_column(row, \"{}\")?", column_name),
+        Ok(SqlColumn::Enum(_)) => format!("get_column::<String>(row, \"{}\")?.map(|v| {}::from(v.as_str()))", column_name, get_enum_name(table_name, column_name)),
+        Ok(SqlColumn::Set(_)) => format!("get_column::<String>(row, \"{}\")?.map(|v| v.split(',').filter(|s| !s.is_empty()).map({}::from).collect())", column_name, get_enum_name(table_name, column_name)),
+        _ => format!("get_column(row, \"{}\")?", column_name),
+    }
+}
+
 fn get_enum_name(table_name: &str, column_name: &str) -> String {
     format!("{}{}", stringUtils::begin_with_upper_case(&stringUtils::to_camel_case(table_name)), stringUtils::begin_with_upper_case(&stringUtils::to_camel_case(column_name)))
 }
@@ -1,13 +1,12 @@
 use std::collections::{HashMap, HashSet};
 use std::fs::OpenOptions;
 use std::io::{BufWriter, Write};
-use std::println;
 use std::path::{Path};
 use sqlx::{AnyConnection, AnyPool, Pool};
 use sqlx_mysql::MySql;
 use crate::codegen::entity::{NamingConvention, GeneratedStructInfo};
 use crate::codegen::utils;
-use crate::codegen::utils::{format_name, prepare_directory, TableRow};
+use crate::codegen::utils::{format_name, prepare_directory, SchemaSource, TableRow};
 use crate::mapping::description::{Column, TableFieldConstructInfo, MysqlColumnDefinition, SqlColumn};
 use crate::utils::stringUtils;
 use std::any::Any;
@@ -60,7 +59,7 @@ impl MappingGenerateConfig{
 }
 
 //generate table mappings to db & table definitions
-pub async fn generate_mappings(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, db_name:&str, config: MappingGenerateConfig){ ///*mappings_out_dir:&str, name_of_crate_holds_enums: String, boolean_columns: &HashMap<String, HashSet<String>>, trait_for_enum_types: &HashMap<&str, &str>*/
+pub async fn generate_mappings<S: SchemaSource>(conn: &S, db_name:&str, config: MappingGenerateConfig){ ///*mappings_out_dir:&str, name_of_crate_holds_enums: String, boolean_columns: &HashMap<String, HashSet<String>>, trait_for_enum_types: &HashMap<&str, &str>*/
     let mappings_out_dir = config.output_dir.clone();
     let crate_and_root_path_of_entity = config.crate_and_root_path_of_entity.clone();
     let boolean_columns = config.boolean_columns.clone();
@@ -71,7 +70,7 @@ pub async fn generate_mappings(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, db_n
     let mappings_out_path = std::path::Path::new(&mappings_out_dir);
     prepare_directory(mappings_out_path);
 
-    let tables = utils::get_tables(conn).await;
+    let tables = conn.get_tables().await;
     //println!("{:#?}",tables);
 
     //collect what has been generated
@@ -84,10 +83,10 @@ pub async fn generate_mappings(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, db_n
                 let generated_entity_info = generate_mapping(conn, table, mappings_out_path, crate_and_root_path_of_entity.clone(), &boolean_columns, entity_field_naming_convention/*, &trait_for_enum_types*/,encrypted_cols.map_or(vec![],|cols|cols.clone())).await;
                 generated_entities.push(generated_entity_info);
             }
-            println!("mappings generated successfully");
+            log::info!("mappings generated successfully");
         }
         Err(error) => {
-            println!("unable to generate entities, error: {:#?}",error);
+            log::error!("unable to generate entities, error: {:#?}",error);
         }
     }
 
@@ -114,11 +113,12 @@ pub async fn generate_mappings(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, db_n
 
 }
 
-async fn generate_mapping(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, table: TableRow, output_path:&Path, crate_and_root_path_of_entity: String,
+async fn generate_mapping<S: SchemaSource>(conn: &S, table: TableRow, output_path:&Path, crate_and_root_path_of_entity: String,
                           boolean_columns: &HashMap<String, Vec<String>>, entity_field_naming_convention: NamingConvention/*, trait_for_enum_types: &HashMap<String, String>*/,
                           encrypted_columns: Vec<&'static str>) -> GeneratedStructInfo{
     let struct_name = format!("{}Table",stringUtils::begin_with_upper_case(&stringUtils::to_camel_case(&table.name)));
-    let fields_result = utils::get_table_fields(conn, &table.name).await;
+    let fields_result = conn.get_table_fields(&table.name).await;
+    let indexes_result = conn.get_table_indexes(&table.name).await;
     let out_file_name_without_ext = format!("{}Table",stringUtils::to_camel_case(&table.name));
     let out_file = output_path.join(format!("{}.rs", out_file_name_without_ext));
 
@@ -176,7 +176,7 @@ async fn generate_mapping(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, table: Ta
             }
         }
         Err(error) => {
-            println!("unable to get fields of table {}, error: {:#?}", table.name, error);
+            log::warn!("unable to get fields of table {}, error: {:#?}", table.name, error);
         }
     }
 
@@ -198,6 +198,19 @@ async fn generate_mapping(conn: & sqlx::pool::Pool<sqlx_mysql::MySql>, table: Ta
 
     writeln!(buf_writer,"impl {} {{", struct_name).expect("Failed to write table mapping code");
 
+    //index names for use_index/force_index/ignore_index
+    match indexes_result {
+        Ok(indexes) => {
+            for index in indexes {
+                let constant_name = index.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }).collect::<String>();
+                writeln!(buf_writer,"    pub const INDEX_{}: &'static str = \"{}\";", constant_name, index).expect("Failed to write table mapping code");
+            }
+        }
+        Err(error) => {
+            log::warn!("unable to get indexes of table {}, error: {:#?}", table.name, error);
+        }
+    }
+
     writeln!(buf_writer,"    pub fn new(alias:Option<&str>) ->Self {{").expect("Failed to write table mapping code");
     //writeln!(buf_writer,"        self._alias = alias;").expect("Failed to write table mapping code");
     writeln!(buf_writer,"        let table_name = alias.clone().unwrap_or(\"{}\");",&table.name).expect("Failed to write table mapping code");
@@ -320,8 +333,10 @@ pub fn get_construct_info_from_column_definition(table_name:&str, mysql_col_defi
                 },
                 SqlColumn::Char(_) => {
                     field_type = "Char".to_string();
-                    name_only_default_value = format!("Char::with_qualified_name(table_name.to_string(),\"{}\".to_string()){}", mysql_col_definition.name_unmodified, if is_encrypted { ".set_encrypted(true)" } else {""});
-                    name_and_value_from_entity_default_value = format!("Char::with_qualified_name_value(table_name.to_string(),\"{}\".to_string(), entity.{}){}", mysql_col_definition.name_unmodified, &entity_field_name, if is_encrypted { ".set_encrypted(true)" } else {""});
+                    //uuid columns of PostgreSQL are Char columns bound as uuid
+                    let marker = if col_definition == "uuid" { ".set_uuid(true)" } else if is_encrypted { ".set_encrypted(true)" } else {""};
+                    name_only_default_value = format!("Char::with_qualified_name(table_name.to_string(),\"{}\".to_string()){}", mysql_col_definition.name_unmodified, marker);
+                    name_and_value_from_entity_default_value = format!("Char::with_qualified_name_value(table_name.to_string(),\"{}\".to_string(), entity.{}){}", mysql_col_definition.name_unmodified, &entity_field_name, marker);
 
                 },
                 SqlColumn::Tinytext(_) => {
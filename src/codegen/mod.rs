@@ -1,3 +1,5 @@
 pub mod entity;
 pub mod mapping;
-pub mod utils;
\ No newline at end of file
+pub mod utils;
+#[cfg(feature = "postgres")]
+pub mod postgres;
@@ -0,0 +1,110 @@
+use sqlx::Row;
+use sqlx_postgres::{PgPool, PgRow};
+use crate::codegen::utils::{SchemaSource, TableFieldRow, TableFullFieldRow, TableRow};
+
+///a column of information_schema.columns, its type translated to the definition MySQL would report
+struct PostgresColumn {
+    name: String,
+    data_type: String,
+    comment: String,
+    nullable: bool,
+    is_primary_key: bool,
+}
+
+//tables of the current schema
+async fn get_postgres_tables(conn: &PgPool) -> Result<Vec<TableRow>, sqlx::Error> {
+    let rows = sqlx::query("select table_name::text from information_schema.tables where table_schema = current_schema() and table_type = 'BASE TABLE' order by table_name")
+        .fetch_all(conn).await?;
+    rows.iter().map(|row| Ok(TableRow { name: row.try_get(0)? })).collect()
+}
+
+async fn get_postgres_columns(conn: &PgPool, table_name: &str) -> Result<Vec<PostgresColumn>, sqlx::Error> {
+    let query = "select c.column_name::text, c.data_type::text, c.udt_name::text, c.character_maximum_length::int4, c.numeric_precision::int4, c.numeric_scale::int4, c.is_nullable::text, \
+                 coalesce(col_description(format('%I.%I', c.table_schema, c.table_name)::regclass, c.ordinal_position::int4), '') as comment, \
+                 exists(select 1 from information_schema.table_constraints tc join information_schema.key_column_usage k on k.constraint_name = tc.constraint_name and k.table_schema = tc.table_schema and k.table_name = tc.table_name \
+                        where tc.constraint_type = 'PRIMARY KEY' and tc.table_schema = c.table_schema and tc.table_name = c.table_name and k.column_name = c.column_name) as is_primary_key \
+                 from information_schema.columns c where c.table_schema = current_schema() and c.table_name = $1 order by c.ordinal_position";
+    let rows = sqlx::query(query).bind(table_name).fetch_all(conn).await?;
+    let mut columns = vec![];
+    for row in rows.iter() {
+        let data_type: String = row.try_get(1)?;
+        let udt_name: String = row.try_get(2)?;
+        let enum_labels = if data_type == "USER-DEFINED" { get_enum_labels(conn, &udt_name).await? } else { vec![] };
+        columns.push(PostgresColumn {
+            name: row.try_get(0)?,
+            data_type: to_mysql_column_definition(&data_type, row, &enum_labels)?,
+            comment: row.try_get(7)?,
+            nullable: row.try_get::<String, _>(6)? == "YES",
+            is_primary_key: row.try_get(8)?,
+        });
+    }
+    Ok(columns)
+}
+
+async fn get_enum_labels(conn: &PgPool, type_name: &str) -> Result<Vec<String>, sqlx::Error> {
+    let rows = sqlx::query("select e.enumlabel::text from pg_type t join pg_enum e on e.enumtypid = t.oid where t.typname = $1 order by e.enumsortorder")
+        .bind(type_name)
+        .fetch_all(conn).await?;
+    rows.iter().map(|row| row.try_get(0)).collect()
+}
+
+//the column definition DESCRIBE of MySQL would report for a postgres column type, so the generators can handle it
+fn to_mysql_column_definition(data_type: &str, row: &PgRow, enum_labels: &[String]) -> Result<String, sqlx::Error> {
+    let length: Option<i32> = row.try_get(3)?;
+    let precision: Option<i32> = row.try_get(4)?;
+    let scale: Option<i32> = row.try_get(5)?;
+    let definition = match data_type {
+        "character varying" => format!("varchar({})", length.unwrap_or(255)),
+        "character" => format!("char({})", length.unwrap_or(1)),
+        "text" => "text".to_string(),
+        "smallint" => "smallint".to_string(),
+        "integer" => "int".to_string(),
+        "bigint" => "bigint".to_string(),
+        "boolean" => "tinyint(1)".to_string(),
+        "numeric" => match (precision, scale) {
+            (Some(precision), Some(scale)) => format!("decimal({},{})", precision, scale),
+            _ => "decimal".to_string(),
+        },
+        "real" => "float".to_string(),
+        "double precision" => "double".to_string(),
+        "date" => "date".to_string(),
+        "time without time zone" | "time with time zone" => "time".to_string(),
+        "timestamp without time zone" => "datetime".to_string(),
+        "timestamp with time zone" => "timestamp".to_string(),
+        "json" | "jsonb" => "json".to_string(),
+        "bytea" => "blob".to_string(),
+        "uuid" => "uuid".to_string(),
+        "USER-DEFINED" if !enum_labels.is_empty() => format!("enum({})", enum_labels.iter().map(|label| format!("'{}'", label.replace('\'', "''"))).collect::<Vec<String>>().join(",")),
+        _ => "text".to_string(),
+    };
+    Ok(definition)
+}
+
+//names of the table's indexes, the primary key keeps its own name such as table_pkey
+async fn get_postgres_indexes(conn: &PgPool, table_name: &str) -> Result<Vec<String>, sqlx::Error> {
+    let rows = sqlx::query("select indexname::text from pg_indexes where schemaname = current_schema() and tablename = $1 order by indexname")
+        .bind(table_name)
+        .fetch_all(conn).await?;
+    rows.iter().map(|row| row.try_get(0)).collect()
+}
+
+///reads the tables of the current schema (search_path) from information_schema
+impl SchemaSource for PgPool {
+    async fn get_tables(&self) -> Result<Vec<TableRow>, sqlx::Error> {
+        get_postgres_tables(self).await
+    }
+
+    async fn get_table_fields(&self, table_name: &str) -> Result<Vec<TableFieldRow>, sqlx::Error> {
+        let columns = get_postgres_columns(self, table_name).await?;
+        Ok(columns.into_iter().map(|column| TableFieldRow { name: column.name, data_type: column.data_type, nullable: column.nullable, is_primary_key: column.is_primary_key }).collect())
+    }
+
+    async fn get_table_full_fields(&self, table_name: &str) -> Result<Vec<TableFullFieldRow>, sqlx::Error> {
+        let columns = get_postgres_columns(self, table_name).await?;
+        Ok(columns.into_iter().map(|column| TableFullFieldRow { name: column.name, comment: column.comment, data_type: column.data_type, nullable: column.nullable, is_primary_key: column.is_primary_key }).collect())
+    }
+
+    async fn get_table_indexes(&self, table_name: &str) -> Result<Vec<String>, sqlx::Error> {
+        get_postgres_indexes(self, table_name).await
+    }
+}
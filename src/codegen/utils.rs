@@ -1,4 +1,5 @@
 use std::fs;
+use std::future::Future;
 
 use sqlx::{AnyConnection, AnyPool, Column, Row};
 use sqlx::any::AnyRow;
@@ -133,6 +134,34 @@ impl From<&MySqlRow> for TableFullFieldRow {
     }
 }
 
+///where the code generators read table definitions from, a MySQL pool or with the postgres feature a PostgreSQL pool.
+///column types are reported the way DESCRIBE of MySQL does, e.g. varchar(32), enum('a','b'), tinyint(1)
+pub trait SchemaSource: Sync {
+    fn get_tables(&self) -> impl Future<Output = Result<Vec<TableRow>, sqlx::Error>> + Send;
+    fn get_table_fields(&self, table_name: &str) -> impl Future<Output = Result<Vec<TableFieldRow>, sqlx::Error>> + Send;
+    fn get_table_full_fields(&self, table_name: &str) -> impl Future<Output = Result<Vec<TableFullFieldRow>, sqlx::Error>> + Send;
+    ///names of the table's indexes, the primary key is named PRIMARY
+    fn get_table_indexes(&self, table_name: &str) -> impl Future<Output = Result<Vec<String>, sqlx::Error>> + Send;
+}
+
+impl SchemaSource for sqlx::pool::Pool<sqlx_mysql::MySql> {
+    async fn get_tables(&self) -> Result<Vec<TableRow>, sqlx::Error> {
+        get_tables(self).await
+    }
+
+    async fn get_table_fields(&self, table_name: &str) -> Result<Vec<TableFieldRow>, sqlx::Error> {
+        get_table_fields(self, table_name).await
+    }
+
+    async fn get_table_full_fields(&self, table_name: &str) -> Result<Vec<TableFullFieldRow>, sqlx::Error> {
+        get_table_full_fields(self, table_name).await
+    }
+
+    async fn get_table_indexes(&self, table_name: &str) -> Result<Vec<String>, sqlx::Error> {
+        get_table_indexes(self, table_name).await
+    }
+}
+
 //get tables' definitions via show tables statement
 pub(crate) async fn get_tables(conn: &sqlx::pool::Pool<sqlx_mysql::MySql>) -> Result<Vec<TableRow>, sqlx::Error> {
     let select_query = sqlx::query("SHOW TABLES");
@@ -164,6 +193,27 @@ pub(crate) async fn get_table_full_fields(conn: &sqlx::pool::Pool<sqlx_mysql::My
     Ok(fields)
 }
 
+//get the names of a table's indexes, the primary key is named PRIMARY
+pub(crate) async fn get_table_indexes(conn: &sqlx::pool::Pool<sqlx_mysql::MySql>, table_name: &str) -> Result<Vec<String>, sqlx::Error> {
+    let query = format!("SHOW INDEX FROM `{}`;",table_name);
+    let select_query = sqlx::query(&query);
+    let rows = select_query.fetch_all(conn).await?;
+    let mut indexes: Vec<String> = vec![];
+    for row in rows.iter() {
+        let index_name = match row.try_get::<String, _>("Key_name") {
+            Ok(value) => value,
+            Err(_) => {
+                let blob_value: Vec<u8> = row.try_get("Key_name")?;
+                String::from_utf8_lossy(&blob_value).to_string()
+            }
+        };
+        if !indexes.contains(&index_name) {
+            indexes.push(index_name);
+        }
+    }
+    Ok(indexes)
+}
+
 pub(crate) fn reserved_field_names() -> Vec<String> {
     vec![
         "as", "async", "await", "break", "const", "continue", "crate", "dyn",
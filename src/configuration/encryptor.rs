@@ -4,6 +4,14 @@ use crate::query::builder::Field;
 
 pub trait Encryptor: Send + Sync {
     fn encrypt(&self, value: String) ->  String;
+    ///the sql expression encrypting the value bound to its single `?` placeholder, e.g. `AES_ENCRYPT(?, 'key')`.
+    ///QueryBuilder binds the plaintext to it instead of rendering encrypt(value) into the statement.
+    ///by default it is encrypt() of a marker replaced by `?`, quotes around the marker included, override it
+    ///when encrypt() doesn't put its value into the expression as it is
+    fn encrypt_bound(&self) -> String {
+        let marker = "rustnq_bound_value";
+        self.encrypt(marker.to_string()).replace(&format!("'{}'", marker), "?").replace(marker, "?")
+    }
     fn decrypt(&self, value: String) ->  String;
     fn decrypt_field(&self, field: Field) ->  String;
 }
@@ -15,4 +23,9 @@ pub fn set_encryptor<E: Encryptor + 'static>(encryptor: E) {
 /// 获取全局的 Encryptor 实例
 pub fn get_encryptor() -> Arc<dyn Encryptor> {
     ENCRYPTOR.get().expect("Encryptor has not been set").clone()
+}
+
+///the expression of the global encryptor for a value bound to its placeholder
+pub(crate) fn encrypt_bound() -> String {
+    get_encryptor().encrypt_bound()
 }
\ No newline at end of file
@@ -0,0 +1,352 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use once_cell::sync::Lazy;
+use sqlx::Error;
+use tracing::Instrument;
+use crate::query::builder::{FieldValue, Operation};
+use crate::query::explain::{explain_sql, QueryPlan};
+use crate::query::metrics::{fingerprint, metrics};
+use crate::query::pool::default_pool;
+
+///hooks called around every statement QueryBuilder sends to the database
+pub trait QueryInterceptor: Send + Sync {
+    fn before_execute(&self, sql: &str, args: &[FieldValue], operation: &Operation);
+    ///rows is the number of fetched rows for a select and the affected rows otherwise, 0 when error is set.
+    ///the duration of a stream is the time spent awaiting its rows, not the time the caller spent between them
+    fn after_execute(&self, sql: &str, duration: Duration, rows: u64, error: Option<&Error>);
+    ///called after a statement that took longer than the slow query threshold, on another task when it is explained.
+    ///plan is None when EXPLAIN could not run, or the statement did not run on the default pool and so was not explained
+    fn on_slow_query(&self, sql: &str, duration: Duration, plan: Option<&QueryPlan>) {
+        log_slow_query(sql, duration, plan);
+    }
+}
+
+///the default report of a slow query: a warning naming the full table scans and filesorts of its plan
+pub fn log_slow_query(sql: &str, duration: Duration, plan: Option<&QueryPlan>) {
+    match plan {
+        Some(plan) => {
+            let mut problems = vec![];
+            if !plan.full_table_scans.is_empty() {
+                problems.push(format!("full table scan on {}", plan.full_table_scans.join(", ")));
+            }
+            if plan.using_filesort {
+                problems.push("filesort".to_string());
+            }
+            if plan.using_temporary_table {
+                problems.push("temporary table".to_string());
+            }
+            log::warn!(target: "rustnq::query", "slow query took {:?}{} # {}", duration, if problems.is_empty() { "".to_string() } else { format!(", {}", problems.join(", ")) }, sql);
+            log::debug!(target: "rustnq::query", "plan of slow query # {}", plan.plan);
+        }
+        None => log::warn!(target: "rustnq::query", "slow query took {:?} # {}", duration, sql),
+    }
+}
+
+///the default interceptor, logs the sql at debug level and failed statements at warn level through the `log` crate.
+///the bound values are not logged since they may hold secrets
+pub struct LogInterceptor;
+
+impl QueryInterceptor for LogInterceptor {
+    fn before_execute(&self, sql: &str, _args: &[FieldValue], operation: &Operation) {
+        log::debug!(target: "rustnq::query", "{:?} # {}", operation, sql);
+    }
+
+    fn after_execute(&self, sql: &str, duration: Duration, rows: u64, error: Option<&Error>) {
+        match error {
+            Some(error) => log::warn!(target: "rustnq::query", "failed after {:?}: {} # {}", duration, error, sql),
+            None => log::debug!(target: "rustnq::query", "{} rows in {:?} # {}", rows, duration, sql),
+        }
+    }
+}
+
+pub static INTERCEPTOR: Lazy<RwLock<Option<Arc<dyn QueryInterceptor>>>> = Lazy::new(|| RwLock::new(Some(Arc::new(LogInterceptor))));
+
+///replaces the interceptor, LogInterceptor is used until this is called
+pub fn set_interceptor<I: QueryInterceptor + 'static>(interceptor: I) {
+    *INTERCEPTOR.write().expect("Failed to acquire write lock.") = Some(Arc::new(interceptor));
+}
+
+///turns interception off entirely, nothing is called or logged around statements anymore
+pub fn disable_interceptor() {
+    *INTERCEPTOR.write().expect("Failed to acquire write lock.") = None;
+}
+
+pub fn get_interceptor() -> Option<Arc<dyn QueryInterceptor>> {
+    INTERCEPTOR.read().expect("Failed to acquire read lock.").clone()
+}
+
+pub static SLOW_QUERY_THRESHOLD: RwLock<Option<Duration>> = RwLock::new(None);
+
+///statements running longer than threshold are reported to on_slow_query, the ones sent through the default pool
+///with their plan from an EXPLAIN run in the background. None (the default) turns the check off
+pub fn set_slow_query_threshold(threshold: Option<Duration>) {
+    *SLOW_QUERY_THRESHOLD.write().expect("Failed to acquire write lock.") = threshold;
+}
+
+pub fn get_slow_query_threshold() -> Option<Duration> {
+    *SLOW_QUERY_THRESHOLD.read().expect("Failed to acquire read lock.")
+}
+
+tokio::task_local! {
+    //set while a statement runs on the default pool, the other executors may be another database or a transaction
+    //whose uncommitted rows a separate connection can't see, so their slow statements are not explained
+    static ON_DEFAULT_POOL: ();
+}
+
+///runs query, which sends its statements through the default pool, so that slow ones are explained there
+pub(crate) async fn on_default_pool<F: Future>(query: F) -> F::Output {
+    ON_DEFAULT_POOL.scope((), query).await
+}
+
+///one statement from before_execute to after_execute, it is traced in a `rustnq.query` span and recorded in metrics().
+///dropped before finish, e.g. a stream the caller stopped reading, it is finished with the rows fetched so far
+pub(crate) struct QueryExecution<'a> {
+    sql: &'a str,
+    args: &'a [FieldValue],
+    fingerprint: String,
+    interceptor: Option<Arc<dyn QueryInterceptor>>,
+    pub span: tracing::Span,
+    start: Instant,
+    fetched_rows: AtomicU64,
+    //time spent awaiting the rows of a stream, without the time the caller holds them between polls
+    awaited: Mutex<Option<Duration>>,
+    finished: AtomicBool,
+}
+
+impl<'a> QueryExecution<'a> {
+    pub fn start(sql: &'a str, args: &'a [FieldValue], operation: &Operation, table: &str) -> Self {
+        let fingerprint = fingerprint(sql);
+        let span = tracing::info_span!("rustnq.query", operation = ?operation, table = table, fingerprint = %fingerprint, rows = tracing::field::Empty, latency_ms = tracing::field::Empty, error = tracing::field::Empty);
+        let interceptor = get_interceptor();
+        if let Some(interceptor) = &interceptor {
+            interceptor.before_execute(sql, args, operation);
+        }
+        QueryExecution { sql, args, fingerprint, interceptor, span, start: Instant::now(), fetched_rows: AtomicU64::new(0), awaited: Mutex::new(None), finished: AtomicBool::new(false) }
+    }
+
+    ///counts a row of a streamed result
+    pub fn fetched_row(&self) {
+        self.fetched_rows.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn fetched_rows(&self) -> u64 {
+        self.fetched_rows.load(Ordering::Relaxed)
+    }
+
+    ///runs a poll of a streamed result, only the time spent in it counts as the duration of the statement
+    pub async fn await_row<R>(&self, poll: impl Future<Output = R>) -> R {
+        let start = Instant::now();
+        let result = poll.instrument(self.span.clone()).await;
+        let mut awaited = self.awaited.lock().unwrap();
+        *awaited = Some(awaited.unwrap_or_default() + start.elapsed());
+        result
+    }
+
+    ///rows is the number of fetched or affected rows, 0 when error is set. only the first call is recorded
+    pub fn finish(&self, rows: u64, error: Option<&Error>) -> Duration {
+        let duration = self.awaited.lock().unwrap().unwrap_or_else(|| self.start.elapsed());
+        if self.finished.swap(true, Ordering::Relaxed) {
+            return duration;
+        }
+        self.span.record("rows", rows);
+        self.span.record("latency_ms", duration.as_secs_f64() * 1000.0);
+        if let Some(error) = error {
+            self.span.record("error", tracing::field::display(error));
+        }
+        metrics().record(&self.fingerprint, duration, error.is_some());
+        if let Some(interceptor) = &self.interceptor {
+            interceptor.after_execute(self.sql, duration, rows, error);
+        }
+        duration
+    }
+
+    ///reports the statement when duration is over the slow query threshold. a statement of the default pool is
+    ///explained on a spawned task first, so the caller doesn't wait for the EXPLAIN
+    pub fn check_slow_query(&self, duration: Duration) {
+        match get_slow_query_threshold() {
+            Some(threshold) if duration > threshold => {}
+            _ => return,
+        }
+        let pool = match ON_DEFAULT_POOL.try_with(|_| ()) {
+            Ok(()) => default_pool().ok(),
+            Err(_) => None,
+        };
+        let runtime = tokio::runtime::Handle::try_current().ok();
+        let (Some(pool), Some(runtime)) = (pool, runtime) else {
+            report_slow_query(self.interceptor.as_deref(), self.sql, duration, None);
+            return;
+        };
+        let (sql, args, interceptor) = (self.sql.to_string(), self.args.to_vec(), self.interceptor.clone());
+        runtime.spawn(async move {
+            let plan = match explain_sql(pool, &sql, &args).await {
+                Ok(plan) => Some(plan),
+                Err(error) => {
+                    log::debug!(target: "rustnq::query", "unable to explain slow query: {} # {}", error, sql);
+                    None
+                }
+            };
+            report_slow_query(interceptor.as_deref(), &sql, duration, plan.as_ref());
+        });
+    }
+}
+
+fn report_slow_query(interceptor: Option<&dyn QueryInterceptor>, sql: &str, duration: Duration, plan: Option<&QueryPlan>) {
+    match interceptor {
+        Some(interceptor) => interceptor.on_slow_query(sql, duration, plan),
+        None => log_slow_query(sql, duration, plan),
+    }
+}
+
+impl Drop for QueryExecution<'_> {
+    fn drop(&mut self) {
+        if !self.finished.load(Ordering::Relaxed) {
+            self.finish(self.fetched_rows(), None);
+        }
+    }
+}
+
+///runs query as a QueryExecution of sql on table, rows counts the result
+pub(crate) async fn intercept<R, F>(sql: &str, args: &[FieldValue], operation: &Operation, table: &str, rows: impl Fn(&R) -> u64, query: F) -> Result<R, Error> where F: Future<Output = Result<R, Error>> {
+    let execution = QueryExecution::start(sql, args, operation, table);
+    let result = query.instrument(execution.span.clone()).await;
+    match &result {
+        Ok(value) => {
+            let duration = execution.finish(rows(value), None);
+            execution.check_slow_query(duration);
+        }
+        Err(error) => {
+            execution.finish(0, Some(error));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::MutexGuard;
+
+    //the interceptor and the slow query threshold are process wide, a test changing them holds the lock of
+    //Globals so the others don't see its values, and gets the previous ones back when it is dropped
+    static GLOBALS: Mutex<()> = Mutex::new(());
+
+    struct Globals {
+        interceptor: Option<Arc<dyn QueryInterceptor>>,
+        threshold: Option<Duration>,
+        _lock: MutexGuard<'static, ()>,
+    }
+
+    fn globals() -> Globals {
+        let lock = GLOBALS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Globals { interceptor: get_interceptor(), threshold: get_slow_query_threshold(), _lock: lock }
+    }
+
+    impl Drop for Globals {
+        fn drop(&mut self) {
+            *INTERCEPTOR.write().expect("Failed to acquire write lock.") = self.interceptor.take();
+            set_slow_query_threshold(self.threshold);
+        }
+    }
+
+    //the slow queries reported to SlowQueryRecorder, with whether they came with a plan
+    static SLOW_QUERIES: Lazy<Mutex<Vec<(String, bool)>>> = Lazy::new(|| Mutex::new(vec![]));
+
+    struct SlowQueryRecorder;
+
+    impl QueryInterceptor for SlowQueryRecorder {
+        fn before_execute(&self, _sql: &str, _args: &[FieldValue], _operation: &Operation) {}
+        fn after_execute(&self, _sql: &str, _duration: Duration, _rows: u64, _error: Option<&Error>) {}
+        fn on_slow_query(&self, sql: &str, _duration: Duration, plan: Option<&QueryPlan>) {
+            SLOW_QUERIES.lock().unwrap().push((sql.to_string(), plan.is_some()));
+        }
+    }
+
+    //the calls made to CallRecorder, other tests may run statements too so they are told apart by their sql
+    static CALLS: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(vec![]));
+
+    struct CallRecorder;
+
+    impl QueryInterceptor for CallRecorder {
+        fn before_execute(&self, sql: &str, args: &[FieldValue], operation: &Operation) {
+            CALLS.lock().unwrap().push(format!("before {:?} {} args={}", operation, sql, args.len()));
+        }
+        fn after_execute(&self, sql: &str, _duration: Duration, rows: u64, error: Option<&Error>) {
+            CALLS.lock().unwrap().push(format!("after {} rows={} failed={}", sql, rows, error.is_some()));
+        }
+    }
+
+    fn calls_of(sql: &str) -> Vec<String> {
+        CALLS.lock().unwrap().iter().filter(|call| call.contains(sql)).cloned().collect()
+    }
+
+    #[tokio::test]
+    async fn the_interceptor_is_called_around_each_statement() {
+        let _globals = globals();
+        set_interceptor(CallRecorder);
+        let sql = "update `intercepted` set `a` = ?";
+        let rows = intercept(sql, &[FieldValue::I32(1)], &Operation::Update_By_Condition, "intercepted", |rows: &u64| *rows, async { Ok(3) }).await.unwrap();
+        assert_eq!(rows, 3);
+        let failed = intercept(sql, &[], &Operation::Update_By_Condition, "intercepted", |rows: &u64| *rows, async { Err(Error::RowNotFound) }).await;
+        assert!(failed.is_err());
+        assert_eq!(calls_of(sql), vec![
+            format!("before Update_By_Condition {} args=1", sql),
+            format!("after {} rows=3 failed=false", sql),
+            format!("before Update_By_Condition {} args=0", sql),
+            format!("after {} rows=0 failed=true", sql),
+        ]);
+
+        disable_interceptor();
+        let sql = "update `not_intercepted` set `a` = ?";
+        intercept(sql, &[], &Operation::Update_By_Condition, "not_intercepted", |rows: &u64| *rows, async { Ok(1) }).await.unwrap();
+        assert!(calls_of(sql).is_empty());
+        assert!(get_interceptor().is_none());
+    }
+
+    #[test]
+    fn slow_queries_off_the_default_pool_are_reported_without_explaining_them() {
+        let _globals = globals();
+        set_interceptor(SlowQueryRecorder);
+        set_slow_query_threshold(Some(Duration::from_millis(10)));
+        let sql = "select `id` from `slow_elsewhere`";
+        let execution = QueryExecution::start(sql, &[], &Operation::Select, "slow_elsewhere");
+        execution.finish(1, None);
+        execution.check_slow_query(Duration::from_millis(5));
+        execution.check_slow_query(Duration::from_millis(20));
+        let reported = SLOW_QUERIES.lock().unwrap().iter().filter(|(reported, _)| reported == sql).cloned().collect::<Vec<(String, bool)>>();
+        assert_eq!(reported, vec![(sql.to_string(), false)]);
+    }
+
+    #[test]
+    fn an_execution_dropped_before_finish_is_recorded_once() {
+        let sql = "select `id` from `dropped_stream`";
+        {
+            let execution = QueryExecution::start(sql, &[], &Operation::Select, "dropped_stream");
+            execution.fetched_row();
+            execution.fetched_row();
+        }
+        let stats = metrics().get(&fingerprint(sql)).unwrap();
+        assert_eq!((stats.count, stats.error_count), (1, 0));
+
+        let sql = "select `id` from `finished_stream`";
+        {
+            let execution = QueryExecution::start(sql, &[], &Operation::Select, "finished_stream");
+            execution.finish(0, Some(&Error::RowNotFound));
+        }
+        let stats = metrics().get(&fingerprint(sql)).unwrap();
+        assert_eq!((stats.count, stats.error_count), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn a_stream_is_timed_by_the_polls_only() {
+        let sql = "select `id` from `slow_consumer`";
+        let execution = QueryExecution::start(sql, &[], &Operation::Select, "slow_consumer");
+        execution.await_row(async {}).await;
+        //the caller holding a row is not the database being slow
+        std::thread::sleep(Duration::from_millis(50));
+        execution.await_row(async { std::thread::sleep(Duration::from_millis(5)) }).await;
+        let duration = execution.finish(1, None);
+        assert!(duration >= Duration::from_millis(5) && duration < Duration::from_millis(50), "{:?}", duration);
+    }
+}
@@ -2,7 +2,9 @@ pub mod processor;
 pub mod field;
 pub mod aes_enc_dec;
 pub mod encryptor;
+pub mod interceptor;
 pub use processor::*;
 pub use field::*;
 pub use aes_enc_dec::*;
-pub use encryptor::*;
\ No newline at end of file
+pub use encryptor::*;
+pub use interceptor::*;
\ No newline at end of file
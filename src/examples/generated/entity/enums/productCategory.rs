@@ -2,7 +2,7 @@ use serde::{{Serialize, Deserialize}};
 
 use std::fmt;
 
-#[derive(Serialize,Deserialize,Clone,Debug,Copy)]
+#[derive(Serialize,Deserialize,Clone,Debug,Copy,Eq,PartialEq,Hash)]
 #[allow(clippy::upper_case_acronyms)]
 #[allow(non_camel_case_types)]
 pub enum ProductCategory {
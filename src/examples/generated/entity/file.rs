@@ -1,8 +1,10 @@
 use serde::Deserialize;
 use serde::Serialize;
+use rustnq::query::builder::{RowMappable, get_column};
 use crate::entity::enums::FileType;
 use crate::entity::enums::FileEntityType;
 use chrono;
+use chrono::Local;
 use crate::entity::enums::FileStatus;
 
 #[derive(Serialize,Deserialize,Clone,Debug)]
@@ -15,9 +17,9 @@ pub struct File<D = ()> {
     pub path:Option<String>,
     pub url:Option<String>,
     pub weight:Option<i32>,
-    #[serde(deserialize_with = "crate::serde::deserialize_datetime")]
-    #[serde(serialize_with = "crate::serde::serialize_datetime")]
-    pub created_on:Option<chrono::NaiveDateTime>,
+    #[serde(deserialize_with = "crate::serde::deserialize_datetime_local")]
+    #[serde(serialize_with = "crate::serde::serialize_datetime_local")]
+    pub created_on:Option<chrono::DateTime<Local>>,
     pub status:Option<FileStatus>,
     pub title:Option<String>,
     pub name:Option<String>,
@@ -41,3 +43,21 @@ impl File {
         }
     }
 }
+impl<D> RowMappable for File<D> {
+    fn from_row(row: &sqlx_mysql::MySqlRow) -> Result<Self, sqlx::Error> {
+        Ok(File {
+            id:get_column(row, "id")?,
+            type_:get_column::<String>(row, "type")?.map(|v| FileType::from(v.as_str())),
+            entity_type:get_column::<String>(row, "entity_type")?.map(|v| FileEntityType::from(v.as_str())),
+            entity_id:get_column(row, "entity_id")?,
+            path:get_column(row, "path")?,
+            url:get_column(row, "url")?,
+            weight:get_column(row, "weight")?,
+            created_on:get_column(row, "created_on")?,
+            status:get_column::<String>(row, "status")?.map(|v| FileStatus::from(v.as_str())),
+            title:get_column(row, "title")?,
+            name:get_column(row, "name")?,
+            _associated: None,
+        })
+    }
+}
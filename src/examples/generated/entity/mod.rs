@@ -4,3 +4,9 @@ pub mod file;
 pub use file::File;
 pub mod product;
 pub use product::Product;
+
+///makes fetch/fetch_one decode the entities with RowMappable instead of going through json, call it once at startup
+pub fn register_row_mappables() {
+    rustnq::query::builder::register_row_mappable::<File>();
+    rustnq::query::builder::register_row_mappable::<Product>();
+}
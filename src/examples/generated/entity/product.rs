@@ -1,10 +1,12 @@
 use serde::Deserialize;
 use serde::Serialize;
+use rustnq::query::builder::{RowMappable, get_column};
 use crate::entity::enums::ProductCategory;
 use crate::entity::enums::ProductStatus;
 use crate::entity::enums::ProductPublished;
 use crate::entity::enums::ProductPromoting;
 use chrono;
+use chrono::Local;
 use crate::entity::enums::ProductTag;
 
 #[derive(Serialize,Deserialize,Clone,Debug)]
@@ -22,12 +24,12 @@ pub struct Product<D = ()> {
     pub status:Option<ProductStatus>,
     pub published:Option<ProductPublished>,
     pub promoting:Option<ProductPromoting>,
-    #[serde(deserialize_with = "crate::serde::deserialize_datetime")]
-    #[serde(serialize_with = "crate::serde::serialize_datetime")]
-    pub created_on:Option<chrono::NaiveDateTime>,
-    #[serde(deserialize_with = "crate::serde::deserialize_datetime")]
-    #[serde(serialize_with = "crate::serde::serialize_datetime")]
-    pub modified_on:Option<chrono::NaiveDateTime>,
+    #[serde(deserialize_with = "crate::serde::deserialize_datetime_local")]
+    #[serde(serialize_with = "crate::serde::serialize_datetime_local")]
+    pub created_on:Option<chrono::DateTime<Local>>,
+    #[serde(deserialize_with = "crate::serde::deserialize_datetime_local")]
+    #[serde(serialize_with = "crate::serde::serialize_datetime_local")]
+    pub modified_on:Option<chrono::DateTime<Local>>,
     pub created_by:Option<String>,
     pub cover_url:Option<String>,
     pub tag:Option<Vec<ProductTag>>,
@@ -59,3 +61,28 @@ impl Product {
         }
     }
 }
+impl<D> RowMappable for Product<D> {
+    fn from_row(row: &sqlx_mysql::MySqlRow) -> Result<Self, sqlx::Error> {
+        Ok(Product {
+            id:get_column(row, "id")?,
+            name:get_column(row, "name")?,
+            summary:get_column(row, "summary")?,
+            description:get_column(row, "description")?,
+            moq:get_column(row, "moq")?,
+            shipping:get_column(row, "shipping")?,
+            material:get_column(row, "material")?,
+            category:get_column::<String>(row, "category")?.map(|v| ProductCategory::from(v.as_str())),
+            supplier_id:get_column(row, "supplier_id")?,
+            status:get_column::<String>(row, "status")?.map(|v| ProductStatus::from(v.as_str())),
+            published:get_column::<String>(row, "published")?.map(|v| ProductPublished::from(v.as_str())),
+            promoting:get_column::<String>(row, "promoting")?.map(|v| ProductPromoting::from(v.as_str())),
+            created_on:get_column(row, "created_on")?,
+            modified_on:get_column(row, "modified_on")?,
+            created_by:get_column(row, "created_by")?,
+            cover_url:get_column(row, "cover_url")?,
+            tag:get_column::<String>(row, "tag")?.map(|v| v.split(',').filter(|s| !s.is_empty()).map(ProductTag::from).collect()),
+            weight:get_column(row, "weight")?,
+            _associated: None,
+        })
+    }
+}
@@ -1,14 +1,13 @@
-use rustnq::mapping::description::SqlColumn;
+use rustnq::mapping::description::{Table, Column, SqlColumn};
 use rustnq::query::builder::Condition;
-use rustnq::mapping::description::Table;
-use shared::entity::File;
-use rustnq::mapping::types::Varchar;
-use shared::entity::enums::FileType;
-use rustnq::mapping::types::Enum;
-use shared::entity::enums::FileEntityType;
-use rustnq::mapping::types::Int;
-use rustnq::mapping::types::Timestamp;
-use shared::entity::enums::FileStatus;
+use crate::entity::File;
+use rustnq::mapping::column_types::Varchar;
+use crate::entity::enums::FileType;
+use rustnq::mapping::column_types::Enum;
+use crate::entity::enums::FileEntityType;
+use rustnq::mapping::column_types::Int;
+use rustnq::mapping::column_types::Timestamp;
+use crate::entity::enums::FileStatus;
 
 #[derive(Clone,Debug)]
 pub struct FileTable {
@@ -23,44 +22,54 @@ pub struct FileTable {
     pub status:Enum<FileStatus>,
     pub title:Varchar,
     pub name:Varchar,
+    _primary_key:Vec<SqlColumn>,
+    _alias:Option<String>
 }
 impl FileTable {
-    pub fn new() ->Self {
+    pub const INDEX_PRIMARY: &'static str = "PRIMARY";
+    pub const INDEX_IDX_ENTITY: &'static str = "idx_entity";
+    pub fn new(alias:Option<&str>) ->Self {
+        let table_name = alias.clone().unwrap_or("file");
         FileTable {
-            id:Varchar::name("id".to_string()),
-            type_:Enum::<FileType>::name("type".to_string()),
-            entity_type:Enum::<FileEntityType>::name("entity_type".to_string()),
-            entity_id:Varchar::name("entity_id".to_string()),
-            path:Varchar::name("path".to_string()),
-            url:Varchar::name("url".to_string()),
-            weight:Int::name("weight".to_string()),
-            created_on:Timestamp::name("created_on".to_string()),
-            status:Enum::<FileStatus>::name("status".to_string()),
-            title:Varchar::name("title".to_string()),
-            name:Varchar::name("name".to_string()),
+            id:Varchar::with_qualified_name(table_name.to_string(),"id".to_string()),
+            type_:Enum::<FileType>::with_qualified_name(table_name.to_string(),"type".to_string()),
+            entity_type:Enum::<FileEntityType>::with_qualified_name(table_name.to_string(),"entity_type".to_string()),
+            entity_id:Varchar::with_qualified_name(table_name.to_string(),"entity_id".to_string()),
+            path:Varchar::with_qualified_name(table_name.to_string(),"path".to_string()),
+            url:Varchar::with_qualified_name(table_name.to_string(),"url".to_string()),
+            weight:Int::with_qualified_name(table_name.to_string(),"weight".to_string()),
+            created_on:Timestamp::with_qualified_name(table_name.to_string(),"created_on".to_string()),
+            status:Enum::<FileStatus>::with_qualified_name(table_name.to_string(),"status".to_string()),
+            title:Varchar::with_qualified_name(table_name.to_string(),"title".to_string()),
+            name:Varchar::with_qualified_name(table_name.to_string(),"name".to_string()),
+            _primary_key:vec![],
+            _alias:alias.map(|s| s.to_string()),
         }
     }
-    pub fn new_with_value(entity:File) ->Self {
+    pub fn new_with_value(entity:File, alias:Option<&str>) ->Self {
+        let table_name = alias.clone().unwrap_or("file");
         FileTable {
-            id:Varchar::name_value("id".to_string(), entity.id),
-            type_:Enum::<FileType>::name_value("type".to_string(), entity.type_),
-            entity_type:Enum::<FileEntityType>::name_value("entity_type".to_string(), entity.entity_type),
-            entity_id:Varchar::name_value("entity_id".to_string(), entity.entity_id),
-            path:Varchar::name_value("path".to_string(), entity.path),
-            url:Varchar::name_value("url".to_string(), entity.url),
-            weight:Int::name_value("weight".to_string(), entity.weight),
-            created_on:Timestamp::name_value("created_on".to_string(), entity.created_on),
-            status:Enum::<FileStatus>::name_value("status".to_string(), entity.status),
-            title:Varchar::name_value("title".to_string(), entity.title),
-            name:Varchar::name_value("name".to_string(), entity.name),
+            id:Varchar::with_qualified_name_value(table_name.to_string(),"id".to_string(), entity.id),
+            type_:Enum::<FileType>::with_qualified_name_value(table_name.to_string(),"type".to_string(), entity.type_),
+            entity_type:Enum::<FileEntityType>::with_qualified_name_value(table_name.to_string(),"entity_type".to_string(), entity.entity_type),
+            entity_id:Varchar::with_qualified_name_value(table_name.to_string(),"entity_id".to_string(), entity.entity_id),
+            path:Varchar::with_qualified_name_value(table_name.to_string(),"path".to_string(), entity.path),
+            url:Varchar::with_qualified_name_value(table_name.to_string(),"url".to_string(), entity.url),
+            weight:Int::with_qualified_name_value(table_name.to_string(),"weight".to_string(), entity.weight),
+            created_on:Timestamp::with_qualified_name_value(table_name.to_string(),"created_on".to_string(), entity.created_on),
+            status:Enum::<FileStatus>::with_qualified_name_value(table_name.to_string(),"status".to_string(), entity.status),
+            title:Varchar::with_qualified_name_value(table_name.to_string(),"title".to_string(), entity.title),
+            name:Varchar::with_qualified_name_value(table_name.to_string(),"name".to_string(), entity.name),
+            _primary_key:vec![],
+            _alias:alias.map(|s| s.to_string()),
         }
     }
 }
 impl Table for FileTable {
     fn name(&self) -> String {
-        "file".to_string()
+        if self._alias.is_some() { format!("file as {}",self._alias.clone().unwrap()) } else {"file".to_string()}
     }
-    fn columns(&self) -> Vec<SqlColumn> {
+    fn all_columns(&self) -> Vec<SqlColumn> {
         vec![
             SqlColumn::Varchar(Some(self.id.clone())),
             SqlColumn::Varchar(Some(self.type_.clone().into())),
@@ -76,8 +85,11 @@ impl Table for FileTable {
         ]
     }
     fn primary_key(&self) -> Vec<SqlColumn> {
-        vec![
+        if self._primary_key.is_empty() { vec![
             SqlColumn::Varchar(Some(self.id.clone())),
-        ]
+        ]} else {self._primary_key.clone()}
+    }
+    fn update_primary_key(&mut self, primary_key: Vec<SqlColumn>) -> () {
+            self._primary_key = primary_key;
     }
 }
@@ -1,17 +1,16 @@
-use rustnq::mapping::description::SqlColumn;
+use rustnq::mapping::description::{Table, Column, SqlColumn};
 use rustnq::query::builder::Condition;
-use rustnq::mapping::description::Table;
-use shared::entity::Product;
-use rustnq::mapping::types::Varchar;
-use rustnq::mapping::types::Int;
-use shared::entity::enums::ProductCategory;
-use rustnq::mapping::types::Enum;
-use shared::entity::enums::ProductStatus;
-use shared::entity::enums::ProductPublished;
-use shared::entity::enums::ProductPromoting;
-use rustnq::mapping::types::Datetime;
-use shared::entity::enums::ProductTag;
-use rustnq::mapping::types::Set;
+use crate::entity::Product;
+use rustnq::mapping::column_types::Varchar;
+use rustnq::mapping::column_types::Int;
+use crate::entity::enums::ProductCategory;
+use rustnq::mapping::column_types::Enum;
+use crate::entity::enums::ProductStatus;
+use crate::entity::enums::ProductPublished;
+use crate::entity::enums::ProductPromoting;
+use rustnq::mapping::column_types::Datetime;
+use crate::entity::enums::ProductTag;
+use rustnq::mapping::column_types::Set;
 
 #[derive(Clone,Debug)]
 pub struct ProductTable {
@@ -33,58 +32,68 @@ pub struct ProductTable {
     pub cover_url:Varchar,
     pub tag:Set<ProductTag>,
     pub weight:Int,
+    _primary_key:Vec<SqlColumn>,
+    _alias:Option<String>
 }
 impl ProductTable {
-    pub fn new() ->Self {
+    pub const INDEX_PRIMARY: &'static str = "PRIMARY";
+    pub const INDEX_IDX_SUPPLIER_ID: &'static str = "idx_supplier_id";
+    pub fn new(alias:Option<&str>) ->Self {
+        let table_name = alias.clone().unwrap_or("product");
         ProductTable {
-            id:Varchar::name("id".to_string()),
-            name:Varchar::name("name".to_string()),
-            summary:Varchar::name("summary".to_string()),
-            description:Varchar::name("description".to_string()),
-            moq:Int::name("moq".to_string()),
-            shipping:Varchar::name("shipping".to_string()),
-            material:Varchar::name("material".to_string()),
-            category:Enum::<ProductCategory>::name("category".to_string()),
-            supplier_id:Varchar::name("supplier_id".to_string()),
-            status:Enum::<ProductStatus>::name("status".to_string()),
-            published:Enum::<ProductPublished>::name("published".to_string()),
-            promoting:Enum::<ProductPromoting>::name("promoting".to_string()),
-            created_on:Datetime::name("created_on".to_string()),
-            modified_on:Datetime::name("modified_on".to_string()),
-            created_by:Varchar::name("created_by".to_string()),
-            cover_url:Varchar::name("cover_url".to_string()),
-            tag:Set::<ProductTag>::name("tag".to_string()),
-            weight:Int::name("weight".to_string()),
+            id:Varchar::with_qualified_name(table_name.to_string(),"id".to_string()),
+            name:Varchar::with_qualified_name(table_name.to_string(),"name".to_string()),
+            summary:Varchar::with_qualified_name(table_name.to_string(),"summary".to_string()),
+            description:Varchar::with_qualified_name(table_name.to_string(),"description".to_string()),
+            moq:Int::with_qualified_name(table_name.to_string(),"moq".to_string()),
+            shipping:Varchar::with_qualified_name(table_name.to_string(),"shipping".to_string()),
+            material:Varchar::with_qualified_name(table_name.to_string(),"material".to_string()),
+            category:Enum::<ProductCategory>::with_qualified_name(table_name.to_string(),"category".to_string()),
+            supplier_id:Varchar::with_qualified_name(table_name.to_string(),"supplier_id".to_string()),
+            status:Enum::<ProductStatus>::with_qualified_name(table_name.to_string(),"status".to_string()),
+            published:Enum::<ProductPublished>::with_qualified_name(table_name.to_string(),"published".to_string()),
+            promoting:Enum::<ProductPromoting>::with_qualified_name(table_name.to_string(),"promoting".to_string()),
+            created_on:Datetime::with_qualified_name(table_name.to_string(),"created_on".to_string()),
+            modified_on:Datetime::with_qualified_name(table_name.to_string(),"modified_on".to_string()),
+            created_by:Varchar::with_qualified_name(table_name.to_string(),"created_by".to_string()),
+            cover_url:Varchar::with_qualified_name(table_name.to_string(),"cover_url".to_string()),
+            tag:Set::<ProductTag>::with_qualified_name(table_name.to_string(),"tag".to_string()),
+            weight:Int::with_qualified_name(table_name.to_string(),"weight".to_string()),
+            _primary_key:vec![],
+            _alias:alias.map(|s| s.to_string()),
         }
     }
-    pub fn new_with_value(entity:Product) ->Self {
+    pub fn new_with_value(entity:Product, alias:Option<&str>) ->Self {
+        let table_name = alias.clone().unwrap_or("product");
         ProductTable {
-            id:Varchar::name_value("id".to_string(), entity.id),
-            name:Varchar::name_value("name".to_string(), entity.name),
-            summary:Varchar::name_value("summary".to_string(), entity.summary),
-            description:Varchar::name_value("description".to_string(), entity.description),
-            moq:Int::name_value("moq".to_string(), entity.moq),
-            shipping:Varchar::name_value("shipping".to_string(), entity.shipping),
-            material:Varchar::name_value("material".to_string(), entity.material),
-            category:Enum::<ProductCategory>::name_value("category".to_string(), entity.category),
-            supplier_id:Varchar::name_value("supplier_id".to_string(), entity.supplier_id),
-            status:Enum::<ProductStatus>::name_value("status".to_string(), entity.status),
-            published:Enum::<ProductPublished>::name_value("published".to_string(), entity.published),
-            promoting:Enum::<ProductPromoting>::name_value("promoting".to_string(), entity.promoting),
-            created_on:Datetime::name_value("created_on".to_string(), entity.created_on),
-            modified_on:Datetime::name_value("modified_on".to_string(), entity.modified_on),
-            created_by:Varchar::name_value("created_by".to_string(), entity.created_by),
-            cover_url:Varchar::name_value("cover_url".to_string(), entity.cover_url),
-            tag:Set::<ProductTag>::name_value("tag".to_string(), entity.tag),
-            weight:Int::name_value("weight".to_string(), entity.weight),
+            id:Varchar::with_qualified_name_value(table_name.to_string(),"id".to_string(), entity.id),
+            name:Varchar::with_qualified_name_value(table_name.to_string(),"name".to_string(), entity.name),
+            summary:Varchar::with_qualified_name_value(table_name.to_string(),"summary".to_string(), entity.summary),
+            description:Varchar::with_qualified_name_value(table_name.to_string(),"description".to_string(), entity.description),
+            moq:Int::with_qualified_name_value(table_name.to_string(),"moq".to_string(), entity.moq),
+            shipping:Varchar::with_qualified_name_value(table_name.to_string(),"shipping".to_string(), entity.shipping),
+            material:Varchar::with_qualified_name_value(table_name.to_string(),"material".to_string(), entity.material),
+            category:Enum::<ProductCategory>::with_qualified_name_value(table_name.to_string(),"category".to_string(), entity.category),
+            supplier_id:Varchar::with_qualified_name_value(table_name.to_string(),"supplier_id".to_string(), entity.supplier_id),
+            status:Enum::<ProductStatus>::with_qualified_name_value(table_name.to_string(),"status".to_string(), entity.status),
+            published:Enum::<ProductPublished>::with_qualified_name_value(table_name.to_string(),"published".to_string(), entity.published),
+            promoting:Enum::<ProductPromoting>::with_qualified_name_value(table_name.to_string(),"promoting".to_string(), entity.promoting),
+            created_on:Datetime::with_qualified_name_value(table_name.to_string(),"created_on".to_string(), entity.created_on),
+            modified_on:Datetime::with_qualified_name_value(table_name.to_string(),"modified_on".to_string(), entity.modified_on),
+            created_by:Varchar::with_qualified_name_value(table_name.to_string(),"created_by".to_string(), entity.created_by),
+            cover_url:Varchar::with_qualified_name_value(table_name.to_string(),"cover_url".to_string(), entity.cover_url),
+            tag:Set::<ProductTag>::with_qualified_name_value(table_name.to_string(),"tag".to_string(), entity.tag),
+            weight:Int::with_qualified_name_value(table_name.to_string(),"weight".to_string(), entity.weight),
+            _primary_key:vec![],
+            _alias:alias.map(|s| s.to_string()),
         }
     }
 }
 impl Table for ProductTable {
     fn name(&self) -> String {
-        "product".to_string()
+        if self._alias.is_some() { format!("product as {}",self._alias.clone().unwrap()) } else {"product".to_string()}
     }
-    fn columns(&self) -> Vec<SqlColumn> {
+    fn all_columns(&self) -> Vec<SqlColumn> {
         vec![
             SqlColumn::Varchar(Some(self.id.clone())),
             SqlColumn::Varchar(Some(self.name.clone())),
@@ -107,8 +116,11 @@ impl Table for ProductTable {
         ]
     }
     fn primary_key(&self) -> Vec<SqlColumn> {
-        vec![
+        if self._primary_key.is_empty() { vec![
             SqlColumn::Varchar(Some(self.id.clone())),
-        ]
+        ]} else {self._primary_key.clone()}
+    }
+    fn update_primary_key(&mut self, primary_key: Vec<SqlColumn>) -> () {
+            self._primary_key = primary_key;
     }
 }
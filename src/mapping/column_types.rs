@@ -1,11 +1,12 @@
 use crate::mapping::description::{Holding, Column, MappedEnum, SqlColumn};
-use crate::query::builder::{Condition, Field, QueryBuilder, SelectField};
+use crate::query::builder::{Condition, Field, FieldValue, Operand, QueryBuilder, SelectField, Window};
 use chrono::{Local, NaiveDate, NaiveTime};
 use serde::{Serialize,Deserialize};
 use std::fmt;
 use std::str::FromStr;
 use crate::utils::date_sub_unit::DateSubUnit;
-use crate::configuration::{encryptor, get_encryptor};
+use crate::query::dialect::{qualify_name, quote_name};
+use crate::configuration::{encrypt_bound, encryptor};
 use chrono::NaiveDateTime;
 
 pub trait And {
@@ -77,16 +78,16 @@ impl<T:Clone+Into<String>> Enum<T>{
 
     pub fn is_null(&self) -> Condition
     {
-        Condition::new(format!("{} IS NULL", self.qualified_name()))
+        Condition::is_null(self.qualified_name())
     }
 
     pub fn equal(&self, input: T) -> Condition
     {
-        Condition::new(format!("{} = '{}'", self.qualified_name(), input.into()))
+        Condition::compare(self.qualified_name(), "=", FieldValue::Enum(input.into()))
     }
     pub fn ne(&self, input: T) -> Condition
     {
-        Condition::new(format!("{} != '{}'", self.qualified_name(), input.into()))
+        Condition::compare(self.qualified_name(), "!=", FieldValue::Enum(input.into()))
     }
 
     pub fn equals(&self, input: Enum<T>) -> Condition
@@ -99,23 +100,19 @@ impl<T:Clone+Into<String>> Enum<T>{
             Holding::Value => format!("'{}'",varchar.value.unwrap().to_string()),
             _ => "".to_string()
         };*/
-        Condition::new(format!("{} = {}", self.qualified_name(), input.qualified_name()))
+        Condition::compare(self.qualified_name(), "=", Operand::Expression(input.qualified_name()))
     }
 
     pub fn in_(&self, input_list: Vec<T>) -> Condition
     {
-        Condition::new(format!("{} in ({})", self.qualified_name(), input_list.into_iter()
-            .map(|input| format!("'{}'", input.into().to_string()))
-            .collect::<Vec<String>>()
-            .join(" , ")))
+        let args = input_list.into_iter().map(|input| FieldValue::Enum(input.into())).collect::<Vec<FieldValue>>();
+        Condition::in_list(self.qualified_name(), args)
     }
 
     pub fn not_in(&self, input_list: Vec<T>) -> Condition
     {
-        Condition::new(format!("{} not in ({})", self.qualified_name(), input_list.into_iter()
-            .map(|input| format!("'{}'", input.into().to_string()))
-            .collect::<Vec<String>>()
-            .join(" , ")))
+        let args = input_list.into_iter().map(|input| FieldValue::Enum(input.into())).collect::<Vec<FieldValue>>();
+        Condition::not_in_list(self.qualified_name(), args)
     }
 
 }
@@ -138,7 +135,7 @@ impl <T:Clone+Into<String>> Column for Enum<T>{
     }
 
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -151,7 +148,9 @@ pub struct Varchar {
     value: Option<String>,
     sub_query: Option<QueryBuilder>,
     holding: Holding,
-    is_encrypted:bool
+    is_encrypted:bool,
+    //the value of an enum column, see FieldValue::Enum
+    is_enum:bool
 }
 
 impl Varchar {
@@ -161,11 +160,11 @@ impl Varchar {
     }*/
 
     pub fn with_name(name: String) -> Self {
-        Varchar {table:None, name:name, alias:None, target:None, value: None ,holding: Holding::Name, sub_query:None,is_encrypted:false }
+        Varchar {table:None, name:name, alias:None, target:None, value: None ,holding: Holding::Name, sub_query:None,is_encrypted:false, is_enum:false }
     }
 
     pub fn with_value(value: Option<String>) -> Self {
-        Varchar {table:None, value:value, alias:None, target:None, name:"".to_string() ,holding: Holding::Value, sub_query:None,is_encrypted:false }
+        Varchar {table:None, value:value, alias:None, target:None, name:"".to_string() ,holding: Holding::Value, sub_query:None,is_encrypted:false, is_enum:false }
     }
 
     pub fn value(&self) -> Option<String> {
@@ -173,19 +172,19 @@ impl Varchar {
     }
 
     pub fn with_name_value(name: String, value: Option<String>) -> Self {
-        Varchar {table:None, name:name, alias:None, target:None, value:value, holding: Holding::Value, sub_query:None,is_encrypted:false }
+        Varchar {table:None, name:name, alias:None, target:None, value:value, holding: Holding::Value, sub_query:None,is_encrypted:false, is_enum:false }
     }
 
     pub fn with_name_query(name: String, sub_query: Option<QueryBuilder>) -> Self {
-        Varchar {table:None, name:name, alias:None, target:None, value:Some("".to_string()), holding: Holding::SubQuery, sub_query:sub_query,is_encrypted:false }
+        Varchar {table:None, name:name, alias:None, target:None, value:Some("".to_string()), holding: Holding::SubQuery, sub_query:sub_query,is_encrypted:false, is_enum:false }
     }
 
     pub fn with_qualified_name(table:String, name: String) -> Self {
-        Varchar {table:Some(table), name:name, alias:None, target:None, value: None ,holding: Holding::Name, sub_query:None, is_encrypted:false }
+        Varchar {table:Some(table), name:name, alias:None, target:None, value: None ,holding: Holding::Name, sub_query:None, is_encrypted:false, is_enum:false }
     }
 
     pub fn with_qualified_name_value(table:String, name: String, value: Option<String>) -> Self {
-        Varchar {table:Some(table), name:name, alias:None, target:None, value:value, holding: Holding::Value, sub_query:None, is_encrypted:false }
+        Varchar {table:Some(table), name:name, alias:None, target:None, value:value, holding: Holding::Value, sub_query:None, is_encrypted:false, is_enum:false }
     }
 
     pub fn set_encrypted(mut self, is_encrypted:bool) -> Self {
@@ -193,6 +192,11 @@ impl Varchar {
         self
     }
 
+    ///whether the value was converted from an Enum, which binds it as FieldValue::Enum
+    pub fn is_enum(&self) -> bool {
+        self.is_enum
+    }
+
     pub fn as_(&mut self, alias:&str) -> Self {
        self.alias = Some(alias.to_string());
        self.clone()
@@ -214,28 +218,26 @@ impl Varchar {
     }
 
 
-    pub fn le<T: ToString>(&self, value: T) -> Condition
+    pub fn le<T: Into<FieldValue>>(&self, value: T) -> Condition
     {
-        Condition::new(format!("{} <= ({})", self.qualified_name(), value.to_string()))
+        build_compare_condition(self.qualified_name(), "<=", value.into())
     }
 
 
-    pub fn gt<T: ToString>(&self, value: T) -> Condition
+    pub fn gt<T: Into<FieldValue>>(&self, value: T) -> Condition
     {
-        Condition::new(format!("{} > ({})", self.qualified_name(), value.to_string()))
+        build_compare_condition(self.qualified_name(), ">", value.into())
     }
 
-    pub fn ge<T: ToString>(&self, value: T) -> Condition
+    pub fn ge<T: Into<FieldValue>>(&self, value: T) -> Condition
     {
-        Condition::new(format!("{} >= ({})", self.qualified_name(), value.to_string()))
+        build_compare_condition(self.qualified_name(), ">=", value.into())
     }
 
     pub fn in_<T:Clone+Into<String>>(&self, input_list: Vec<T>) -> Condition
     {
-        Condition::new(format!("{} in ({})", self.qualified_name(), input_list.into_iter()
-            .map(|input| format!("'{}'", input.into().to_string()))
-            .collect::<Vec<String>>()
-            .join(" , ")))
+        let args = input_list.into_iter().map(|input| FieldValue::String(input.into())).collect::<Vec<FieldValue>>();
+        Condition::in_list(self.qualified_name(), args)
     }
 
     pub fn optional_as(mut self, alias:Option<String>) -> Self {
@@ -273,12 +275,23 @@ impl Varchar {
 
     pub fn like(&self, pattern: String) -> Condition
     {
-        Condition::new(format!("{} LIKE '{}'", self.qualified_name(), pattern))
+        Condition::compare(self.qualified_name(), "LIKE", FieldValue::String(pattern))
+    }
+
+    ///case-insensitive like, ILIKE on PostgreSQL
+    pub fn ilike(&self, pattern: String) -> Condition
+    {
+        Condition::compare(self.qualified_name(), "ILIKE", FieldValue::String(pattern))
     }
 
     pub fn not_like(&self, pattern: String) -> Condition
     {
-        Condition::new(format!("{} NOT LIKE '{}'", self.qualified_name(), pattern))
+        Condition::compare(self.qualified_name(), "NOT LIKE", FieldValue::String(pattern))
+    }
+
+    pub fn not_ilike(&self, pattern: String) -> Condition
+    {
+        Condition::compare(self.qualified_name(), "NOT ILIKE", FieldValue::String(pattern))
     }
 
     pub fn desc(&self) -> SelectField
@@ -287,19 +300,19 @@ impl Varchar {
     }
     pub fn is_null(&self) -> Condition
     {
-        Condition::new(format!("{} IS NULL", self.qualified_name()))
+        Condition::is_null(self.qualified_name())
     }
     pub fn is_not_null(&self) -> Condition
     {
-        Condition::new(format!("{} IS NOT NULL", self.qualified_name()))
+        Condition::is_not_null(self.qualified_name())
     }
     pub fn is_not_empty(&self) -> Condition
     {
-        Condition::new(format!("{} !=''", self.qualified_name()))
+        Condition::compare(self.qualified_name(), "!=", FieldValue::String(String::new()))
     }
     pub fn is_empty(&self) -> Condition
     {
-        Condition::new(format!("{} =''", self.qualified_name()))
+        Condition::compare(self.qualified_name(), "=", FieldValue::String(String::new()))
     }
     pub fn ne<T>(&self, input: T) -> Condition
     where
@@ -307,11 +320,11 @@ impl Varchar {
     {
         let varchar = input.into();
         let output = match varchar.holding {
-            Holding::Name => varchar.qualified_name(),
-            Holding::Value => format!("'{}'",varchar.value.unwrap().to_string()),
-            _ => "".to_string()
+            Holding::Name => Operand::Expression(varchar.qualified_name()),
+            Holding::Value => Operand::Value(FieldValue::from(varchar.value.clone())),
+            _ => Operand::Expression("".to_string())
         };
-        Condition::new(format!("{} != {}", self.qualified_name(), output))
+        Condition::compare(self.qualified_name(), "!=", output)
     }
 }
 
@@ -368,6 +381,7 @@ impl<T:Clone> From<Enum<T>> for Varchar where std::string::String: From<T> {
             sub_query: a_enum.sub_query,
             holding: a_enum.holding,
             is_encrypted: a_enum.is_encrypted,
+            is_enum: true,
         }
     }
 }
@@ -392,6 +406,7 @@ impl<T> From<Set<T>> for Varchar where  std::string::String: From<T>,T:Clone {
             sub_query: set.sub_query,
             holding: set.holding,
             is_encrypted: set.is_encrypted,
+            is_enum: false,
         }
     }
 }
@@ -408,6 +423,7 @@ impl From<&Int> for Varchar {
             sub_query: i.sub_query.clone(),
             holding: i.holding.clone(),
             is_encrypted: i.is_encrypted,
+            is_enum: false,
         }
     }
 }
@@ -424,6 +440,7 @@ impl From<Int> for Varchar {
             sub_query: i.sub_query.clone(),
             holding: i.holding.clone(),
             is_encrypted: i.is_encrypted,
+            is_enum: false,
         }
     }
 }
@@ -439,6 +456,7 @@ impl From<Longtext> for Varchar  {
             sub_query: value.sub_query.clone(),
             holding: value.holding.clone(),
             is_encrypted: value.is_encrypted,
+            is_enum: false,
         }
     }
 }
@@ -454,6 +472,7 @@ impl From<&Longtext> for Varchar   {
             sub_query: value.sub_query.clone(),
             holding: value.holding.clone(),
             is_encrypted: value.is_encrypted, 
+            is_enum: false,
         }
     }
 }
@@ -493,7 +512,7 @@ impl Column for Varchar {
         }
     }*/
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -506,53 +525,48 @@ pub struct Char{
     value: Option<String>,
     sub_query: Option<QueryBuilder>,
     holding: Holding,
-    is_encrypted:bool
+    is_encrypted:bool,
+    //a PostgreSQL uuid column, see FieldValue::Uuid
+    is_uuid:bool
 }
 
-fn encrypt_value(value:String) -> String {
-    let encryptor = get_encryptor();
-    encryptor.encrypt(value)
+fn build_compare_condition(qualified_name:String, operator:&str, value:FieldValue) -> Condition {
+    Condition::compare(qualified_name, operator, value)
 }
 
 fn build_equal_condition_for_string_type(self_table:Option<String>, self_name:String,self_is_encrypted: bool, input_holding:Holding,input_table:Option<String>, input_name:String,input_value:Option<String>) -> Condition {
-    let mut self_name = self_name.clone();
-    if self_table.is_some() {
-        self_name = format!("{}.{}",self_table.unwrap(),self_name);
-    }
-    let mut input_name = input_name.clone();
-    if input_table.is_some() {
-        input_name = format!("{}.{}",input_table.unwrap(),input_name);
-    }
-    let output = match input_holding {
-        Holding::Name => format!(" = {}",input_name),
+    let self_name = if let Some(table) = self_table {qualify_name(&table, &self_name)} else {quote_name(&self_name)};
+    let input_name = if let Some(table) = input_table {qualify_name(&table, &input_name)} else {quote_name(&input_name)};
+    match input_holding {
+        Holding::Name => Condition::compare(self_name, "=", Operand::Expression(input_name)),
         Holding::Value => match input_value {
-            Some(value) => format!(" = {}",if self_is_encrypted {encrypt_value(value)} else {format!("'{}'",value)}),
-            None => "is null".to_string()
+            //an encrypted column is compared with the encryptor expression, the plaintext is bound to its placeholder
+            Some(value) => Condition::compare(self_name, "=", if self_is_encrypted {Operand::Bound{expression: encrypt_bound(), args: vec![FieldValue::String(value)]}} else {Operand::Value(FieldValue::String(value))}),
+            None => Condition::is_null(self_name)
         },
-        _ => "build_equal_condition_for_string_type to do ".to_string() //subquery
-    };
-    Condition::new(format!("{} {}", self_name, output))
+        _ => Condition::Invalid(format!("{} can not be compared with a subquery by equal", self_name))
+    }
 }
 
 impl Char {
     pub fn with_name(name: String) -> Self {
-        Char {table:None,  name:name, alias: None, target: None, value: None ,holding: Holding::Name, sub_query:None,is_encrypted:false }
+        Char {table:None,  name:name, alias: None, target: None, value: None ,holding: Holding::Name, sub_query:None,is_encrypted:false, is_uuid:false }
     }
 
     pub fn with_value(value: Option<String>) -> Self {
-        Char {table:None,  value:value, name:"".to_string() , target: None, holding: Holding::Value, sub_query:None, alias: None,is_encrypted:false }
+        Char {table:None,  value:value, name:"".to_string() , target: None, holding: Holding::Value, sub_query:None, alias: None,is_encrypted:false, is_uuid:false }
     }
 
     pub fn with_name_value(name: String, value: Option<String>) -> Self {
-        Char {table:None,  name:name, alias: None, target: None, value:value, holding: Holding::Value, sub_query:None,is_encrypted:false }
+        Char {table:None,  name:name, alias: None, target: None, value:value, holding: Holding::Value, sub_query:None,is_encrypted:false, is_uuid:false }
     }
 
     pub fn with_qualified_name(table:String, name: String) -> Self {
-        Char {table:Some(table), name:name, alias: None, target: None, value: None ,holding: Holding::Name, sub_query:None, is_encrypted:false }
+        Char {table:Some(table), name:name, alias: None, target: None, value: None ,holding: Holding::Name, sub_query:None, is_encrypted:false, is_uuid:false }
     }
 
     pub fn with_qualified_name_value(table:String, name: String, value: Option<String>) -> Self {
-        Char {table:Some(table), name:name, value:value, target: None, holding: Holding::Value, sub_query:None, alias: None,is_encrypted:false }
+        Char {table:Some(table), name:name, value:value, target: None, holding: Holding::Value, sub_query:None, alias: None,is_encrypted:false, is_uuid:false }
     }
 
     pub fn value(&self) -> Option<String> {
@@ -564,6 +578,16 @@ impl Char {
         self
     }
 
+    ///marks a uuid column of PostgreSQL, its values are bound as uuid instead of text
+    pub fn set_uuid(mut self, is_uuid:bool) -> Self {
+        self.is_uuid = is_uuid;
+        self
+    }
+
+    pub fn is_uuid(&self) -> bool {
+        self.is_uuid
+    }
+
     pub fn as_(&mut self, alias:&str) -> Self {
         self.alias = Some(alias.to_string());
         self.clone()
@@ -574,12 +598,21 @@ impl Char {
         T: Into<Char>,
     {
         let input = input.into();
-        build_equal_condition_for_string_type(self.table.clone(), self.name.clone(), self.is_encrypted.clone(), input.holding, input.table,input.name,input.value)
+        match (self.is_uuid, input.holding, input.value) {
+            (true, Holding::Value, Some(value)) => Condition::compare(self.qualified_name(), "=", FieldValue::Uuid(value)),
+            (_, holding, value) => build_equal_condition_for_string_type(self.table.clone(), self.name.clone(), self.is_encrypted.clone(), holding, input.table,input.name,value),
+        }
     }
 
     pub fn like(&self, pattern: String) -> Condition
     {
-        Condition::new(format!("{} LIKE '{}'", self.qualified_name(), pattern))
+        Condition::compare(self.qualified_name(), "LIKE", FieldValue::String(pattern))
+    }
+
+    ///case-insensitive like, ILIKE on PostgreSQL
+    pub fn ilike(&self, pattern: String) -> Condition
+    {
+        Condition::compare(self.qualified_name(), "ILIKE", FieldValue::String(pattern))
     }
 }
 
@@ -600,7 +633,7 @@ impl Column for Char {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -661,7 +694,13 @@ impl crate::mapping::column_types::Tinytext {
 
     pub fn like(&self, pattern: String) -> Condition
     {
-        Condition::new(format!("{} LIKE '{}'", self.qualified_name(), pattern))
+        Condition::compare(self.qualified_name(), "LIKE", FieldValue::String(pattern))
+    }
+
+    ///case-insensitive like, ILIKE on PostgreSQL
+    pub fn ilike(&self, pattern: String) -> Condition
+    {
+        Condition::compare(self.qualified_name(), "ILIKE", FieldValue::String(pattern))
     }
 }
 
@@ -682,7 +721,7 @@ impl Column for Tinytext {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -743,7 +782,13 @@ impl crate::mapping::column_types::Text {
 
     pub fn like(&self, pattern: String) -> Condition
     {
-        Condition::new(format!("{} LIKE '{}'", self.qualified_name(), pattern))
+        Condition::compare(self.qualified_name(), "LIKE", FieldValue::String(pattern))
+    }
+
+    ///case-insensitive like, ILIKE on PostgreSQL
+    pub fn ilike(&self, pattern: String) -> Condition
+    {
+        Condition::compare(self.qualified_name(), "ILIKE", FieldValue::String(pattern))
     }
 }
 
@@ -764,7 +809,7 @@ impl Column for crate::mapping::column_types::Text {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -779,6 +824,7 @@ impl From<Text> for Varchar {
             sub_query: v.sub_query,
             holding: v.holding,
             is_encrypted: v.is_encrypted,
+            is_enum: false,
         }
     }
 }
@@ -845,7 +891,13 @@ impl crate::mapping::column_types::Mediumtext {
 
     pub fn like(&self, pattern: String) -> Condition
     {
-        Condition::new(format!("{} LIKE '{}'", self.qualified_name(), pattern))
+        Condition::compare(self.qualified_name(), "LIKE", FieldValue::String(pattern))
+    }
+
+    ///case-insensitive like, ILIKE on PostgreSQL
+    pub fn ilike(&self, pattern: String) -> Condition
+    {
+        Condition::compare(self.qualified_name(), "ILIKE", FieldValue::String(pattern))
     }
 }
 
@@ -866,7 +918,7 @@ impl Column for crate::mapping::column_types::Mediumtext {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -932,7 +984,13 @@ impl crate::mapping::column_types::Longtext {
 
     pub fn like(&self, pattern: String) -> Condition
     {
-        Condition::new(format!("{} LIKE '{}'", self.qualified_name(), pattern))
+        Condition::compare(self.qualified_name(), "LIKE", FieldValue::String(pattern))
+    }
+
+    ///case-insensitive like, ILIKE on PostgreSQL
+    pub fn ilike(&self, pattern: String) -> Condition
+    {
+        Condition::compare(self.qualified_name(), "ILIKE", FieldValue::String(pattern))
     }
 }
 
@@ -977,7 +1035,7 @@ impl Column for crate::mapping::column_types::Longtext {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -1033,24 +1091,24 @@ impl Int {
         self.clone()
     }
 
-    pub fn gt<T: ToString>(&self, value: T) -> Condition
+    pub fn gt<T: Into<FieldValue>>(&self, value: T) -> Condition
     {
-        Condition::new(format!("{} > ({})", self.qualified_name(), value.to_string()))
+        build_compare_condition(self.qualified_name(), ">", value.into())
     }
 
-    pub fn lt<T: ToString>(&self, value: T) -> Condition
+    pub fn lt<T: Into<FieldValue>>(&self, value: T) -> Condition
     {
-        Condition::new(format!("{} < ({})", self.qualified_name(), value.to_string()))
+        build_compare_condition(self.qualified_name(), "<", value.into())
     }
 
-    pub fn ge<T: ToString>(&self, value: T) -> Condition
+    pub fn ge<T: Into<FieldValue>>(&self, value: T) -> Condition
     {
-        Condition::new(format!("{} >= ({})", self.qualified_name(), value.to_string()))
+        build_compare_condition(self.qualified_name(), ">=", value.into())
     }
 
-    pub fn le<T: ToString>(&self, value: T) -> Condition
+    pub fn le<T: Into<FieldValue>>(&self, value: T) -> Condition
     {
-        Condition::new(format!("{} <= ({})", self.qualified_name(), value.to_string()))
+        build_compare_condition(self.qualified_name(), "<=", value.into())
     }
 
     pub fn div<T: std::fmt::Display>(&mut self, value: T) -> Self {
@@ -1064,27 +1122,27 @@ impl Int {
     {
         let int = input.into();
         let output = match int.holding {
-            Holding::Name => int.qualified_name(),
-            Holding::Value => format!("'{}'",int.value.unwrap().to_string()),
-            _ => "".to_string()
+            Holding::Name => Operand::Expression(int.qualified_name()),
+            Holding::Value => Operand::Value(FieldValue::from(int.value)),
+            _ => Operand::Expression("".to_string())
         };
-        Condition::new(format!("{} = {}", self.qualified_name(), output))
+        Condition::compare(self.qualified_name(), "=", output)
     }
     pub fn is_null(&self) -> Condition
     {
-        Condition::new(format!("{} IS NULL", self.qualified_name()))
+        Condition::is_null(self.qualified_name())
     }
     pub fn is_not_null(&self) -> Condition
     {
-        Condition::new(format!("{} IS NOT NULL", self.qualified_name()))
+        Condition::is_not_null(self.qualified_name())
     }
     pub fn is_not_empty(&self) -> Condition
     {
-        Condition::new(format!("{} !=''", self.qualified_name()))
+        Condition::compare(self.qualified_name(), "!=", FieldValue::String(String::new()))
     }
     pub fn is_empty(&self) -> Condition
     {
-        Condition::new(format!("{} =''", self.qualified_name()))
+        Condition::compare(self.qualified_name(), "=", FieldValue::String(String::new()))
     }
     pub fn holding(&self) -> Holding {
         self.holding.clone()
@@ -1140,7 +1198,7 @@ impl Column for Int {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -1246,7 +1304,7 @@ impl Column for crate::mapping::column_types::Year {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -1303,11 +1361,8 @@ impl<T:Clone+Into<String>> Set<T> {
     }
 
     pub fn find_in_set(&mut self, value:String) -> Condition {
-        let mut self_name = self.name.clone();
-        if self.table.is_some() {
-            self_name = format!("{}.{}",self.table.clone().unwrap(),self_name);
-        }
-        Condition::new(format!("FIND_IN_SET('{}', {}) > 0", value, self_name))
+        let self_name = self.qualified_name();
+        Condition::with_args(format!("FIND_IN_SET(?, {}) > 0", self_name), vec![FieldValue::String(value)])
     }
 
     pub fn value_as_string(&self) -> Option<String> {
@@ -1319,10 +1374,8 @@ impl<T:Clone+Into<String>> Set<T> {
     
     pub fn in_(&self, input_list: Vec<T>) -> Condition
     {
-        Condition::new(format!("{} in ({})", self.qualified_name(), input_list.into_iter()
-            .map(|input| format!("'{}'", input.into().to_string()))
-            .collect::<Vec<String>>()
-            .join(" , ")))
+        let args = input_list.into_iter().map(|input| FieldValue::String(input.into())).collect::<Vec<FieldValue>>();
+        Condition::in_list(self.qualified_name(), args)
     }
 }
 
@@ -1343,7 +1396,7 @@ impl <T:Clone+Into<String>> Column for Set<T> {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -1413,11 +1466,11 @@ impl crate::mapping::column_types::Boolean {
     {
         let tinyint = input.into();
         let output = match tinyint.holding {
-            Holding::Name => {tinyint.qualified_name()}
-            Holding::Value => {format!("{}", if tinyint.value.unwrap_or_default() {"1"} else {"0"})}
-            _=> "".to_string()
+            Holding::Name => {Operand::Expression(tinyint.qualified_name())}
+            Holding::Value => {Operand::Value(FieldValue::Bool(tinyint.value.unwrap_or_default()))}
+            _=> Operand::Expression("".to_string())
         };
-        Condition::new(format!("{} = {}", self.qualified_name(), output))
+        Condition::compare(self.qualified_name(), "=", output)
     }
 }
 
@@ -1438,6 +1491,7 @@ impl From<Boolean> for Varchar {
             sub_query: v.sub_query,
             holding: v.holding,
             is_encrypted: v.is_encrypted,
+            is_enum: false,
         }
     }
 }
@@ -1459,7 +1513,7 @@ impl Column for crate::mapping::column_types::Boolean {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -1521,11 +1575,11 @@ impl Tinyint {
     {
         let tinyint = input.into();
         let output = match tinyint.holding {
-            Holding::Name => {tinyint.qualified_name()}
-            Holding::Value => {format!("{}", tinyint.value.unwrap_or_default())}
-            _=> "".to_string()
+            Holding::Name => {Operand::Expression(tinyint.qualified_name())}
+            Holding::Value => {Operand::Value(FieldValue::from(tinyint.value.unwrap_or_default()))}
+            _=> Operand::Expression("".to_string())
         };
-        Condition::new(format!("{} = {}", self.qualified_name(), output))
+        Condition::compare(self.qualified_name(), "=", output)
     }
     pub fn holding(&self) -> Holding {
         self.holding.clone()
@@ -1552,7 +1606,7 @@ impl Column for Tinyint {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -1567,6 +1621,7 @@ impl From<Tinyint> for Varchar {
             sub_query: v.sub_query,
             holding: v.holding,
             is_encrypted: v.is_encrypted,
+            is_enum: false,
         }
     }
 }
@@ -1651,7 +1706,7 @@ impl Column for crate::mapping::column_types::Smallint {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -1705,6 +1760,51 @@ impl Bigint {
         self.target = Some(target.to_string());
         self.clone()
     }
+
+    pub fn gt<T: Into<FieldValue>>(&self, value: T) -> Condition
+    {
+        build_compare_condition(self.qualified_name(), ">", value.into())
+    }
+
+    pub fn lt<T: Into<FieldValue>>(&self, value: T) -> Condition
+    {
+        build_compare_condition(self.qualified_name(), "<", value.into())
+    }
+
+    pub fn ge<T: Into<FieldValue>>(&self, value: T) -> Condition
+    {
+        build_compare_condition(self.qualified_name(), ">=", value.into())
+    }
+
+    pub fn le<T: Into<FieldValue>>(&self, value: T) -> Condition
+    {
+        build_compare_condition(self.qualified_name(), "<=", value.into())
+    }
+
+    pub fn equal<T: Into<FieldValue>>(&self, value: T) -> Condition
+    {
+        build_compare_condition(self.qualified_name(), "=", value.into())
+    }
+
+    pub fn ne<T: Into<FieldValue>>(&self, value: T) -> Condition
+    {
+        build_compare_condition(self.qualified_name(), "!=", value.into())
+    }
+
+    pub fn is_null(&self) -> Condition
+    {
+        Condition::is_null(self.qualified_name())
+    }
+
+    pub fn is_not_null(&self) -> Condition
+    {
+        Condition::is_not_null(self.qualified_name())
+    }
+
+    ///turns an aggregate into a window function, e.g. a running total `sum(t.amount()).over(partition_by(..).order_by(..))`
+    pub fn over(&self, window: Window) -> Self {
+        Bigint::with_name(format!("{} OVER ({})", self.qualified_name(), window.to_string()))
+    }
 }
 
 impl Column for Bigint {
@@ -1724,7 +1824,7 @@ impl Column for Bigint {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -1828,7 +1928,7 @@ impl Column for crate::mapping::column_types::BigintUnsigned {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -1902,7 +2002,7 @@ impl Column for crate::mapping::column_types::Numeric {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -1976,7 +2076,7 @@ impl Column for crate::mapping::column_types::Float {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -2050,7 +2150,7 @@ impl Column for crate::mapping::column_types::Double {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -2107,11 +2207,16 @@ impl crate::mapping::column_types::Decimal {
     }
 
     pub fn is_not_null(&self) -> Condition {
-        Condition::new(format!("{} IS NOT NULL", self.qualified_name()))
+        Condition::is_not_null(self.qualified_name())
     }
 
     pub fn is_null(&self) -> Condition {
-        Condition::new(format!("{} IS NULL", self.qualified_name()))
+        Condition::is_null(self.qualified_name())
+    }
+
+    ///turns an aggregate into a window function, e.g. a moving average `avg(t.amount()).over(partition_by(..))`
+    pub fn over(&self, window: Window) -> Self {
+        Decimal::with_name(format!("{} OVER ({})", self.qualified_name(), window.to_string()))
     }
 }
 
@@ -2132,30 +2237,30 @@ impl Column for crate::mapping::column_types::Decimal {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
 // 为Decimal类型添加比较方法
 impl crate::mapping::column_types::Decimal {
-    pub fn ge<T: ToString>(&self, value: T) -> Condition
+    pub fn ge<T: Into<FieldValue>>(&self, value: T) -> Condition
     {
-        Condition::new(format!("{} >= ({})", self.qualified_name(), value.to_string()))
+        build_compare_condition(self.qualified_name(), ">=", value.into())
     }
 
-    pub fn gt<T: ToString>(&self, value: T) -> Condition
+    pub fn gt<T: Into<FieldValue>>(&self, value: T) -> Condition
     {
-        Condition::new(format!("{} > ({})", self.qualified_name(), value.to_string()))
+        build_compare_condition(self.qualified_name(), ">", value.into())
     }
 
-    pub fn lt<T: ToString>(&self, value: T) -> Condition
+    pub fn lt<T: Into<FieldValue>>(&self, value: T) -> Condition
     {
-        Condition::new(format!("{} < ({})", self.qualified_name(), value.to_string()))
+        build_compare_condition(self.qualified_name(), "<", value.into())
     }
 
-    pub fn le<T: ToString>(&self, value: T) -> Condition
+    pub fn le<T: Into<FieldValue>>(&self, value: T) -> Condition
     {
-        Condition::new(format!("{} <= ({})", self.qualified_name(), value.to_string()))
+        build_compare_condition(self.qualified_name(), "<=", value.into())
     }
 }
 
@@ -2171,6 +2276,7 @@ impl From<Decimal> for Varchar {
             sub_query: v.sub_query,
             holding: v.holding,
             is_encrypted: v.is_encrypted,
+            is_enum: false,
         }
     }
 }
@@ -2236,38 +2342,39 @@ impl crate::mapping::column_types::Date {
         self.clone()
     }
 
-    pub fn ge<T: ToString>(&self, value: T) -> Condition
+    pub fn ge<T: Into<FieldValue>>(&self, value: T) -> Condition
     {
-        Condition::new(format!("{} >= '{}'", self.qualified_name(), value.to_string()))
+        build_compare_condition(self.qualified_name(), ">=", value.into())
     }
 
-    pub fn equal<T: ToString>(&self, value: T) -> Condition
+    pub fn equal<T: Into<FieldValue>>(&self, value: T) -> Condition
     {
-        Condition::new(format!("{} = '{}'", self.qualified_name(), value.to_string()))
+        build_compare_condition(self.qualified_name(), "=", value.into())
     }
 
-    pub fn gt<T: ToString>(&self, value: T) -> Condition
+    pub fn gt<T: Into<FieldValue>>(&self, value: T) -> Condition
     {
-        Condition::new(format!("{} > '{}'", self.qualified_name(), value.to_string()))
+        build_compare_condition(self.qualified_name(), ">", value.into())
     }
 
-    pub fn lt<T: ToString>(&self, value: T) -> Condition
+    pub fn lt<T: Into<FieldValue>>(&self, value: T) -> Condition
     {
-        Condition::new(format!("{} < '{}'", self.qualified_name(), value.to_string()))
+        build_compare_condition(self.qualified_name(), "<", value.into())
     }
 
+    ///compares with a column or an expression such as `curdate()` instead of a value
     pub fn lt_<T: Into<SelectField>>(&self, value: T) -> Condition
     {
-        Condition::new(format!("{} < '{}'", self.qualified_name(), value.into().to_string()))
+        Condition::compare(self.qualified_name(), "<", Operand::Expression(value.into().to_string()))
     }
 
-    pub fn le<T: ToString>(&self, value: T) -> Condition
+    pub fn le<T: Into<FieldValue>>(&self, value: T) -> Condition
     {
-        Condition::new(format!("{} <= '{}'", self.qualified_name(), value.to_string()))
+        build_compare_condition(self.qualified_name(), "<=", value.into())
     }
 
     pub fn between(&self, date1: chrono::NaiveDate,date2: chrono::NaiveDate) -> Condition {
-        Condition::new(format!("{} BETWEEN '{}' AND '{}'", self.qualified_name(), date1.format("%Y-%m-%d").to_string(), date2.format("%Y-%m-%d").to_string()))
+        Condition::with_args(format!("{} BETWEEN ? AND ?", self.qualified_name()), vec![FieldValue::Date(date1), FieldValue::Date(date2)])
     }
 }
 
@@ -2283,6 +2390,7 @@ impl From<&Date> for Varchar {
             sub_query: i.sub_query.clone(),
             holding: i.holding.clone(),
             is_encrypted: i.is_encrypted,
+            is_enum: false,
         }
     }
 }
@@ -2299,6 +2407,7 @@ impl From<Date> for Varchar {
             sub_query: i.sub_query.clone(),
             holding: i.holding.clone(),
             is_encrypted: i.is_encrypted,
+            is_enum: false,
         }
     }
 }
@@ -2315,6 +2424,7 @@ impl From<&Datetime> for Varchar {
             sub_query: i.sub_query.clone(),
             holding: i.holding.clone(),
             is_encrypted: i.is_encrypted,
+            is_enum: false,
         }
     }
 }
@@ -2331,6 +2441,7 @@ impl From<Datetime> for Varchar {
             sub_query: i.sub_query.clone(),
             holding: i.holding.clone(),
             is_encrypted: i.is_encrypted,
+            is_enum: false,
         }
     }
 }
@@ -2368,7 +2479,7 @@ impl Column for crate::mapping::column_types::Date {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -2451,7 +2562,7 @@ impl Column for crate::mapping::column_types::Time {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -2512,9 +2623,9 @@ impl crate::mapping::column_types::Datetime {
         SelectField::Field(Field::new(&*self.table(), &format!("{} desc", &*self.name().to_string()), self.target.clone(), self.alias(), self.is_encrypted()))
     }
 
-    pub fn gt<T: ToString>(&self, value: T) -> Condition
+    pub fn gt<T: Into<FieldValue>>(&self, value: T) -> Condition
     {
-        Condition::new(format!("{} > ({})", self.qualified_name(), value.to_string()))
+        build_compare_condition(self.qualified_name(), ">", value.into())
     }
     pub fn holding(&self) -> Holding {
         self.holding.clone()
@@ -2541,7 +2652,7 @@ impl Column for crate::mapping::column_types::Datetime {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -2604,17 +2715,11 @@ impl crate::mapping::column_types::Timestamp {
         // 获取结束时间戳 (当天的结束时间)
         let timestamp_end = NaiveDateTime::new(input, NaiveTime::from_hms(23, 59, 59));
 
-        // 将它们格式化为字符串
-        let timestamp_begin_str = timestamp_begin.to_string();
-        let timestamp_end_str = timestamp_end.to_string();
-
         // 创建 Condition 对象
-        Condition::new(format!(
-            "{} BETWEEN '{}' AND '{}'",
-            self.qualified_name(),
-            timestamp_begin_str,
-            timestamp_end_str
-        ))
+        Condition::with_args(format!(
+            "{} BETWEEN ? AND ?",
+            self.qualified_name()
+        ), vec![FieldValue::Datetime(timestamp_begin), FieldValue::Datetime(timestamp_end)])
     }
 
     pub fn desc(&self) -> SelectField{
@@ -2634,6 +2739,7 @@ impl From<Timestamp> for Varchar {
             sub_query: i.sub_query.clone(),
             holding: i.holding.clone(),
             is_encrypted: i.is_encrypted,
+            is_enum: false,
         }
     }
 }
@@ -2655,7 +2761,7 @@ impl Column for crate::mapping::column_types::Timestamp {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -2721,7 +2827,13 @@ impl crate::mapping::column_types::Json {
 
     pub fn like(&self, pattern: String) -> Condition
     {
-        Condition::new(format!("{} LIKE '{}'", self.qualified_name(), pattern))
+        Condition::compare(self.qualified_name(), "LIKE", FieldValue::String(pattern))
+    }
+
+    ///case-insensitive like, ILIKE on PostgreSQL
+    pub fn ilike(&self, pattern: String) -> Condition
+    {
+        Condition::compare(self.qualified_name(), "ILIKE", FieldValue::String(pattern))
     }
 }
 
@@ -2742,7 +2854,7 @@ impl Column for crate::mapping::column_types::Json {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
 
@@ -2815,6 +2927,6 @@ impl Column for crate::mapping::column_types::Blob {
         self.is_encrypted
     }
     fn qualified_name(&self) -> String {
-        if self.table.is_some() {format!("{}.{}",self.table.clone().unwrap(),self.name.clone())} else {self.name.clone()}
+        if self.table.is_some() {qualify_name(&self.table.clone().unwrap(), &self.name)} else {quote_name(&self.name)}
     }
 }
\ No newline at end of file
@@ -6,13 +6,21 @@ use crate::mapping::column_types::*;
 use crate::utils::stringUtils;
 use serde::{Serialize,Deserialize};
 use sqlx::encode::IsNull;
-use crate::query::builder::{Field, SelectField};
+use crate::query::builder::{Cte, Field, FieldValue, SelectField};
 
 pub trait Table{
     fn name(&self) -> String;
     fn all_columns(&self) -> Vec<SqlColumn>;
     fn primary_key(&self) -> Vec<SqlColumn>;
     fn update_primary_key(&mut self,primary_key:Vec<SqlColumn>)->();
+    ///values bound to `?` placeholders inside name(), e.g. a derived table built by as_table
+    fn args(&self) -> Vec<FieldValue> {
+        vec![]
+    }
+    ///ctes this table depends on, they are rendered in the WITH clause of the query using the table
+    fn ctes(&self) -> Vec<Cte> {
+        vec![]
+    }
     //fn as_(&self,alias:&str) -> Table;
 }
 
@@ -129,6 +137,8 @@ impl FromStr for SqlColumn {
         let typeName = stringUtils::begin_with_upper_case(&stringUtils::to_camel_case(&mysql_col_type.replace(" ", "_")));
         match typeName.as_str() {
             "Char" => Ok(SqlColumn::Char(None)),
+            //uuid of PostgreSQL, a Char marked with set_uuid
+            "Uuid" => Ok(SqlColumn::Char(None)),
             "Varchar" => Ok(SqlColumn::Varchar(None)),
             "Tinytext" => Ok(SqlColumn::Tinytext(None)),
             "Text" => Ok(SqlColumn::Text(None)),
@@ -3,27 +3,40 @@ use std::{fmt, fmt::write, format, result};
 use std::collections::{HashMap, HashSet};
 use std::io::Write;
 use serde::{Deserialize, Serialize};
-use sqlx::{Column as MysqlColumn, Error, Row, TypeInfo, Value};
+use sqlx::{Column as MysqlColumn, Error, Row, TypeInfo, Value, ValueRef};
 use crate::mapping::column_types::{Boolean, Bigint, Char, Tinytext, Varchar, Date, Decimal, Timestamp, Int, Datetime, Enum, Time, Tinyint};
 use sqlx_mysql::{MySqlQueryResult, MySqlRow, MySqlTypeInfo};
 use sqlx_mysql::{MySqlPool, MySqlPoolOptions};
 use url::Url;
 use lazy_static::lazy_static;
 use std::sync::Mutex;
+use std::sync::RwLock;
+use std::sync::Arc;
+use std::any::{Any, TypeId};
+use once_cell::sync::Lazy;
 use serde_json::{json, Number};
 use serde_json::Value as JsonValue;
 use std::future::Future;
-use chrono::{DateTime, NaiveTime, Utc};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use rust_decimal::prelude::ToPrimitive;
 use sqlx::Executor;
 use sqlx::Database;
-use crate::configuration::{encryptor, get_encryptor};
-use crate::query::pool::{POOL};
+use sqlx::Arguments;
+use sqlx_mysql::{MySql, MySqlArguments, MySqlConnection};
+use crate::query::transaction::TransactionHandle;
+use crate::configuration::{encrypt_bound, encryptor, get_encryptor, intercept, on_default_pool, QueryExecution};
+use crate::query::pool::default_pool;
 use crate::utils::stringUtils::to_camel_case;
 use crate::mapping::description::SqlColumn;
-use crate::query::builder::JoinType::{INNER, LEFT};
+use crate::query::builder::JoinType::{CROSS, INNER, LEFT, RIGHT};
 use crate::query::select;
-use crate::result::PagingData;
+use crate::result::{CursorPagingData, PagingData};
+use crate::query::explain::{explain_sql, QueryPlan};
+use crate::query::dialect::{get_dialect, is_rendering, qualify_name, quote_name, quote_table_name, render_with, Clause, Dialect, MySqlDialect};
+use base64::Engine;
+use futures::{Stream, TryStreamExt};
+use async_stream::try_stream;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use crate::mapping::column_types::Set;
 use crate::query::builder::JoinType::STRAIGHT;
 
@@ -36,6 +49,7 @@ pub enum BuildErrorType {
     MissingPrimaryKeyValue,
     MissingFields,
     MissingValues,
+    InvalidCondition,
     OtherError,
 }
 
@@ -51,46 +65,266 @@ impl QueryBuildError{
             error, message
         }
     }
+
+    ///what is wrong with the query, to tell e.g. a missing condition apart from an invalid one
+    pub fn error(&self) -> &BuildErrorType {
+        &self.error
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
 }
 
+///decodes a row straight into the type, without the json round trip of fetch/fetch_one.
+///generated entities implement it, fetch/fetch_one take this path for types registered with register_row_mappable,
+///fetch_mapped/fetch_one_mapped for any implementation
 pub trait RowMappable{
-    fn from_row(row: &MySqlRow) -> Self;
+    fn from_row(row: &MySqlRow) -> Result<Self, Error> where Self: Sized;
+}
+
+type RowMapper = fn(&MySqlRow) -> Result<Box<dyn Any + Send>, Error>;
+
+pub static ROW_MAPPERS: Lazy<RwLock<HashMap<TypeId, RowMapper>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+
+///makes fetch/fetch_one decode T with RowMappable::from_row instead of going through json.
+///the generated entity module registers all its entities in register_row_mappables(), call it once at startup
+pub fn register_row_mappable<T: RowMappable + Send + 'static>() {
+    fn map_row<T: RowMappable + Send + 'static>(row: &MySqlRow) -> Result<Box<dyn Any + Send>, Error> {
+        T::from_row(row).map(|value| Box::new(value) as Box<dyn Any + Send>)
+    }
+    ROW_MAPPERS.write().expect("Failed to acquire write lock.").insert(TypeId::of::<T>(), map_row::<T>);
+}
+
+fn get_row_mapper<T: 'static>() -> Option<RowMapper> {
+    ROW_MAPPERS.read().expect("Failed to acquire read lock.").get(&TypeId::of::<T>()).copied()
+}
+
+fn downcast_row<T: 'static>(value: Box<dyn Any + Send>) -> Result<T, Error> {
+    value.downcast::<T>().map(|value| *value).map_err(|_| Error::Decode("the row mapper registered for the type returned another type".into()))
 }
 
+///reads column `name` for a RowMappable implementation, a column missing from the select list is None.
+///the sql type is not checked against T, so unsigned/year columns decode into the entity integer types as well
+pub fn get_column<'r, T>(row: &'r MySqlRow, name: &str) -> Result<Option<T>, Error> where T: sqlx::Decode<'r, MySql> + sqlx::Type<MySql> {
+    match row.try_get_unchecked::<Option<T>, _>(name) {
+        Err(Error::ColumnNotFound(_)) => Ok(None),
+        result => result,
+    }
+}
+
+///reads column `name` as text and parses it, e.g. a DECIMAL column into f64
+pub fn get_parsed_column<T: std::str::FromStr>(row: &MySqlRow, name: &str) -> Result<Option<T>, Error> where T::Err: std::fmt::Display {
+    match get_column::<String>(row, name)? {
+        Some(value) => value.parse::<T>().map(Some).map_err(|e| Error::ColumnDecode { index: name.to_string(), source: e.to_string().into() }),
+        None => Ok(None),
+    }
+}
+
+///right hand side of a comparison
 #[derive(Debug,Clone)]
-pub struct Condition {
-    pub query: String,
+pub enum Operand {
+    ///a column or sql expression, rendered as is
+    Expression(String),
+    ///a value bound to a `?` placeholder
+    Value(FieldValue),
+    ///a sql expression whose `?` placeholders are bound to args, e.g. the encryptor applied to a value
+    Bound { expression: String, args: Vec<FieldValue> },
+}
+
+impl From<FieldValue> for Operand {
+    fn from(value: FieldValue) -> Self { Operand::Value(value) }
+}
+
+impl Operand {
+    fn to_sql(&self, args: &mut Vec<FieldValue>) -> String {
+        match self {
+            Operand::Expression(expression) => expression.clone(),
+            Operand::Value(value) => value.to_placeholder(args),
+            Operand::Bound { expression, args: bound_args } => {
+                args.extend(bound_args.iter().cloned());
+                expression.clone()
+            }
+        }
+    }
+}
+
+const COMPARISON_OPERATORS: [&str; 11] = ["=", "!=", "<>", ">", ">=", "<", "<=", "LIKE", "NOT LIKE", "ILIKE", "NOT ILIKE"];
+
+///a where/having/on predicate, kept as a tree so it can be inspected and rewritten until the query is built
+#[derive(Debug,Clone)]
+pub enum Condition {
+    ///`left operator right`, operator is one of =, !=, <>, >, >=, <, <=, LIKE, NOT LIKE
+    Comparison { left: String, operator: String, right: Operand },
+    ///`expression IS NULL`, or IS NOT NULL when negated
+    IsNull { expression: String, negated: bool },
+    ///`expression IN (?, ...)`, or NOT IN when negated
+    In { expression: String, values: Vec<FieldValue>, negated: bool },
+    ///`EXISTS (query)`, or NOT EXISTS when negated
+    Exists { query: Box<QueryBuilder>, negated: bool },
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+    ///sql text with `?` placeholders bound to args in order, it is validated when the query is built
+    Raw { sql: String, args: Vec<FieldValue> },
+    ///a condition that could not be formed, building a query with it fails with this message
+    Invalid(String),
 }
 
 impl Condition {
     pub fn new(query: String) -> Condition {
-        Condition { query:if Self::is_valid_condition(query.clone()) {query} else {"0>1".to_string()} }
+        Self::with_args(query, vec![])
+    }
+
+    ///condition with `?` placeholders, args are bound in the same order when the query is executed
+    pub fn with_args(query: String, args: Vec<FieldValue>) -> Condition {
+        Condition::Raw { sql: query, args }
+    }
+
+    pub fn compare<T: Into<Operand>>(left: String, operator: &str, right: T) -> Condition {
+        Condition::Comparison { left, operator: operator.to_string(), right: right.into() }
+    }
+
+    pub fn is_null(expression: String) -> Condition {
+        Condition::IsNull { expression, negated: false }
+    }
+
+    pub fn is_not_null(expression: String) -> Condition {
+        Condition::IsNull { expression, negated: true }
+    }
+
+    pub fn in_list(expression: String, values: Vec<FieldValue>) -> Condition {
+        Condition::In { expression, values, negated: false }
+    }
+
+    pub fn not_in_list(expression: String, values: Vec<FieldValue>) -> Condition {
+        Condition::In { expression, values, negated: true }
+    }
+
+    pub fn exists(query: QueryBuilder) -> Condition {
+        Condition::Exists { query: Box::new(query), negated: false }
+    }
+
+    pub fn not_exists(query: QueryBuilder) -> Condition {
+        Condition::Exists { query: Box::new(query), negated: true }
+    }
+
+    pub fn not(self) -> Condition {
+        Condition::Not(Box::new(self))
     }
 
     pub fn and(self, other: Condition) -> Condition {
-        Condition {
-            query: format!("({}) AND ({})", self.query, if Self::is_valid_condition(other.query.clone()) {other.query} else {"0>1".to_string()}),
+        match self {
+            Condition::And(mut conditions) => {
+                conditions.push(other);
+                Condition::And(conditions)
+            }
+            condition => Condition::And(vec![condition, other]),
         }
     }
 
-    pub fn and_not_exists(self, other: QueryBuilder) -> Condition {
-        let other_query = other.build().unwrap_or_default();
-        Condition {
-            query: format!("({}) AND NOT EXISTS ({})", self.query, if Self::is_valid_condition(other_query.clone()) {other_query} else {"0>1".to_string()}),
+    ///self AND other when other is present, e.g. a filter from an optional request parameter
+    pub fn and_opt(self, other: Option<Condition>) -> Condition {
+        match other {
+            Some(other) => self.and(other),
+            None => self,
         }
     }
 
-    pub fn and_exists(self, other: QueryBuilder) -> Condition {
-        let other_query = other.build().unwrap_or_default();
-        Condition {
-            query: format!("({}) AND EXISTS ({})", self.query, if Self::is_valid_condition(other_query.clone()) {other_query} else {"0>1".to_string()}),
+    ///every condition must hold, an empty list matches every row
+    pub fn all_of(mut conditions: Vec<Condition>) -> Condition {
+        if conditions.len() == 1 {
+            return conditions.remove(0);
+        }
+        Condition::And(conditions)
+    }
+
+    ///at least one condition must hold. an empty list matches every row like all_of,
+    ///so a group of optional filters that are all absent does not filter anything
+    pub fn any_of(mut conditions: Vec<Condition>) -> Condition {
+        match conditions.len() {
+            0 => Condition::And(vec![]),
+            1 => conditions.remove(0),
+            _ => Condition::Or(conditions),
         }
     }
 
+    pub fn and_not_exists(self, other: QueryBuilder) -> Condition {
+        self.and(Condition::not_exists(other))
+    }
+
+    pub fn and_exists(self, other: QueryBuilder) -> Condition {
+        self.and(Condition::exists(other))
+    }
+
     pub fn or(self, other: Condition) -> Condition {
-        Condition {
-            query: format!("({}) OR ({})", self.query, if Self::is_valid_condition(other.query.clone()) {other.query} else {"0>1".to_string()}),
+        match self {
+            Condition::Or(mut conditions) => {
+                conditions.push(other);
+                Condition::Or(conditions)
+            }
+            condition => Condition::Or(vec![condition, other]),
+        }
+    }
+
+    ///renders the condition and collects its bind values into args, in the order of their placeholders
+    pub fn to_sql(&self, args: &mut Vec<FieldValue>) -> Result<String, QueryBuildError> {
+        match self {
+            Condition::Comparison { left, operator, right } => {
+                let operator = operator.to_uppercase();
+                if !COMPARISON_OPERATORS.contains(&operator.as_str()) {
+                    return Err(QueryBuildError::new(BuildErrorType::InvalidCondition, format!("unsupported comparison operator {} on {}", operator, left)));
+                }
+                //case-insensitive LIKE is rendered the way the dialect spells it
+                let operator = match operator.as_str() {
+                    "ILIKE" => get_dialect().ilike_operator().to_string(),
+                    "NOT ILIKE" => format!("NOT {}", get_dialect().ilike_operator()),
+                    _ => operator,
+                };
+                Ok(format!("{} {} {}", left, operator, right.to_sql(args)))
+            }
+            Condition::IsNull { expression, negated } => Ok(format!("{} IS {}NULL", expression, if *negated { "NOT " } else { "" })),
+            Condition::In { expression, values, negated } => {
+                if values.is_empty() {
+                    //nothing is in an empty list
+                    return Ok(if *negated { "1 = 1" } else { "1 = 0" }.to_string());
+                }
+                let placeholders = values.iter().map(|value| value.to_placeholder(args)).collect::<Vec<String>>();
+                Ok(format!("{} {}in ({})", expression, if *negated { "not " } else { "" }, placeholders.join(" , ")))
+            }
+            Condition::Exists { query, negated } => {
+                let (query_string, query_args) = query.build()?;
+                args.extend(query_args);
+                Ok(format!("{}exists ({})", if *negated { "not " } else { "" }, query_string))
+            }
+            Condition::And(conditions) => Self::join_to_sql(conditions, "AND", "1 = 1", args),
+            Condition::Or(conditions) => Self::join_to_sql(conditions, "OR", "1 = 0", args),
+            Condition::Not(condition) => Ok(format!("NOT ({})", condition.to_sql(args)?)),
+            Condition::Raw { sql, args: raw_args } => {
+                if !Self::is_valid_condition(sql.clone()) {
+                    return Err(QueryBuildError::new(BuildErrorType::InvalidCondition, format!("invalid condition: {}", sql)));
+                }
+                let placeholders = count_placeholders(sql);
+                if placeholders != raw_args.len() {
+                    return Err(QueryBuildError::new(BuildErrorType::InvalidCondition, format!("condition {} has {} placeholders but {} values", sql, placeholders, raw_args.len())));
+                }
+                args.extend(raw_args.iter().cloned());
+                Ok(sql.clone())
+            }
+            Condition::Invalid(message) => Err(QueryBuildError::new(BuildErrorType::InvalidCondition, message.clone())),
+        }
+    }
+
+    //(a) AND (b), identity is rendered for an empty list
+    fn join_to_sql(conditions: &Vec<Condition>, operator: &str, identity: &str, args: &mut Vec<FieldValue>) -> Result<String, QueryBuildError> {
+        if conditions.is_empty() {
+            return Ok(identity.to_string());
         }
+        let mut parts = vec![];
+        for condition in conditions {
+            parts.push(format!("({})", condition.to_sql(args)?));
+        }
+        Ok(parts.join(&format!(" {} ", operator)))
     }
 
     fn is_valid_condition(condition:String) -> bool {
@@ -106,6 +340,7 @@ impl Condition {
                 c == ':' || c== '!' ||
                 c == '/' || c== '*' ||
                 c == '%' || c== '+' ||
+                c == '?' ||
                 c == '（' || c == '）' ||
                 c == '，' || c == '。' ||
             c.is_digit(10) ||  // 允许数字
@@ -125,38 +360,159 @@ impl Condition {
     }
 }
 
+///the condition with its `?` placeholders followed by their values, for logs and debugging only, use to_sql to execute it
 impl fmt::Display for Condition {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.query)
+        let mut args = vec![];
+        match self.to_sql(&mut args) {
+            Ok(sql) if args.is_empty() => write!(f, "{}", sql),
+            Ok(sql) => write!(f, "{} {:?}", sql, args),
+            Err(error) => write!(f, "<{}>", error.message),
+        }
+    }
+}
+
+///renders a list of conditions joined with AND, OR and raw conditions are parenthesized so they keep their meaning
+fn conditions_to_sql(conditions: &Vec<Condition>, args: &mut Vec<FieldValue>) -> Result<String, QueryBuildError> {
+    let mut parts = vec![];
+    for condition in conditions {
+        let sql = condition.to_sql(args)?;
+        match condition {
+            Condition::Or(_) | Condition::Raw { .. } if conditions.len() > 1 => parts.push(format!("({})", sql)),
+            _ => parts.push(sql),
+        }
     }
+    Ok(parts.join(" AND "))
+}
+
+//an error naming what the dialect of the statement being built can't express
+fn require(clause: Clause, what: &str) -> Result<(), QueryBuildError> {
+    let dialect = get_dialect();
+    if dialect.supports(clause) {
+        Ok(())
+    }else {
+        Err(QueryBuildError::new(BuildErrorType::OtherError, format!("{} is not supported by the {} dialect", what, dialect.name())))
+    }
+}
+
+//a column assigned in SET, databases without multi-table update only take its name
+fn set_column(field: &SelectField) -> String {
+    match field {
+        SelectField::Field(field) if !get_dialect().supports(Clause::JoinedWrite) => quote_name(&field.name),
+        field => field.clone().to_string(),
+    }
+}
+
+//`?` outside of quoted strings and identifiers
+pub(crate) fn placeholder_positions(sql: &str) -> Vec<usize> {
+    let mut positions = vec![];
+    let mut quote: Option<char> = None;
+    for (index, c) in sql.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' || c == '`' => quote = Some(c),
+            None if c == '?' => positions.push(index),
+            None => {}
+        }
+    }
+    positions
+}
+
+fn count_placeholders(sql: &str) -> usize {
+    placeholder_positions(sql).len()
 }
 
 #[derive(Debug,Clone)]
 pub enum Operation{
     Select,Insert, Update_By_PrimaryKey,
-    Update_By_Condition,Insert_Or_Update,Delete
+    Update_By_Condition,Insert_Or_Update,Delete,InsertSelect
 }
 
 #[derive(Debug,Clone)]
 pub(crate) struct TargetTable{
     pub name:String,
     pub columns:Vec<SqlColumn>,
-    pub primary_key:Vec<SqlColumn>
+    pub primary_key:Vec<SqlColumn>,
+    pub args:Vec<FieldValue>,
+    pub ctes:Vec<Cte>,
+    pub index_hints:Vec<IndexHint>,
 }
 
 impl TargetTable {
     pub fn new(table: & dyn Table) -> TargetTable {
         TargetTable{
-            name: table.name(),
+            name: quote_table_name(&table.name()),
             columns: table.all_columns(),
             primary_key: table.primary_key(),
+            args: table.args(),
+            ctes: table.ctes(),
+            index_hints: vec![],
+        }
+    }
+
+    ///whether table, the qualifier of a column, refers to this table by its name or its alias, quoted or not
+    fn is_referred_by(&self, table: &str) -> bool {
+        let unquote = |name: &str| name.trim().replace(['`', '"'], "");
+        let table = unquote(table);
+        match self.name.to_lowercase().rfind(" as ") {
+            Some(index) => unquote(&self.name[index + 4..]) == table || unquote(&self.name[..index]) == table,
+            None => unquote(&self.name) == table,
+        }
+    }
+
+    ///name followed by its index hints, as rendered in from and joins
+    fn name_with_hints(&self) -> String {
+        if self.index_hints.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{} {}", self.name, self.index_hints.iter().map(|hint| hint.to_string()).collect::<Vec<String>>().join(" "))
+        }
+    }
+}
+
+///USE/FORCE/IGNORE INDEX of a table in from or a join, mapping tables have a constant per index, e.g. `ProductTable::INDEX_IDX_SUPPLIER_ID`
+#[derive(Debug,Clone)]
+pub enum IndexHint {
+    Use(Vec<String>),Force(Vec<String>),Ignore(Vec<String>)
+}
+impl fmt::Display for IndexHint {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        //index names are quoted like columns, e.g. `PRIMARY`
+        let quoted = |indexes: &Vec<String>| indexes.iter().map(|index| quote_name(index)).collect::<Vec<String>>().join(", ");
+        match self {
+            IndexHint::Use(indexes) => write!(f,"USE INDEX ({})", quoted(indexes)),
+            IndexHint::Force(indexes) => write!(f,"FORCE INDEX ({})", quoted(indexes)),
+            IndexHint::Ignore(indexes) => write!(f,"IGNORE INDEX ({})", quoted(indexes)),
+        }
+    }
+}
+
+///MySQL 8 optimizer hints, rendered as /*+ ... */ after select. tables are named by their alias if they have one
+#[derive(Debug,Clone)]
+pub enum OptimizerHint {
+    ///milliseconds before the server aborts the select
+    MaxExecutionTime(u64),
+    JoinOrder(Vec<String>),
+    JoinFixedOrder,
+    Index(String, Vec<String>),
+    NoIndex(String, Vec<String>),
+}
+impl fmt::Display for OptimizerHint {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OptimizerHint::MaxExecutionTime(milliseconds) => write!(f,"MAX_EXECUTION_TIME({})", milliseconds),
+            OptimizerHint::JoinOrder(tables) => write!(f,"JOIN_ORDER({})", tables.join(", ")),
+            OptimizerHint::JoinFixedOrder => write!(f,"JOIN_FIXED_ORDER()"),
+            OptimizerHint::Index(table, indexes) => write!(f,"INDEX({} {})", table, indexes.join(", ")),
+            OptimizerHint::NoIndex(table, indexes) => write!(f,"NO_INDEX({} {})", table, indexes.join(", ")),
         }
     }
 }
 
 #[derive(Debug,Clone)]
 pub enum JoinType {
-    LEFT,INNER,STRAIGHT
+    LEFT,INNER,STRAIGHT,RIGHT,CROSS
 }
 impl fmt::Display for JoinType {
     fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
@@ -164,6 +520,8 @@ impl fmt::Display for JoinType {
             LEFT => write!(f,"LEFT JOIN"),
             INNER => write!(f,"INNER JOIN"),
             STRAIGHT => write!(f,"STRAIGHT_JOIN"),
+            RIGHT => write!(f,"RIGHT JOIN"),
+            CROSS => write!(f,"CROSS JOIN"),
         }
     }
 }
@@ -172,13 +530,14 @@ pub struct TableJoin{
     target_table: TargetTable,
     join_type:JoinType,
     condition:Option<Condition>,
+    using:Vec<String>,
 }
 
 impl TableJoin{
     pub fn new(target_table: TargetTable, join_type:JoinType, condition:Option<Condition>) -> Self {
         TableJoin{
             target_table:target_table.clone(), join_type,
-            condition
+            condition, using: vec![],
         }
     }
 
@@ -186,6 +545,107 @@ impl TableJoin{
         self.condition = Some(condition);
         self.clone()
     }
+
+    ///joins on the columns with the same name in both tables, rendered as USING (...)
+    pub fn apply_using(&mut self, columns: Vec<String>) -> TableJoin {
+        self.using = columns;
+        self.clone()
+    }
+
+    ///renders the join clause and collects its bind values into args
+    fn to_sql(&self, args: &mut Vec<FieldValue>) -> Result<String, QueryBuildError> {
+        args.extend(self.target_table.args.clone());
+        if let CROSS = self.join_type {
+            return Ok(format!(" {} {} ", self.join_type.to_string(), self.target_table.name_with_hints()));
+        }
+        if !self.using.is_empty() {
+            return Ok(format!(" {} {} USING ({}) ", self.join_type.to_string(), self.target_table.name_with_hints(), self.using.iter().map(|column| quote_name(column)).collect::<Vec<String>>().join(", ")));
+        }
+        match &self.condition {
+            Some(condition) => {
+                Ok(format!(" {} {} ON {} ", self.join_type.to_string(), self.target_table.name_with_hints(), condition.to_sql(args)?))
+            }
+            None => Err(QueryBuildError::new(BuildErrorType::MissingCondition, format!("please call on() or using() for {} {}", self.join_type.to_string(), self.target_table.name))),
+        }
+    }
+}
+
+#[derive(Debug,Clone)]
+pub enum SetOperator {
+    Union,UnionAll,Intersect,Except
+}
+impl fmt::Display for SetOperator {
+    fn fmt(&self,f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SetOperator::Union => write!(f,"UNION"),
+            SetOperator::UnionAll => write!(f,"UNION ALL"),
+            SetOperator::Intersect => write!(f,"INTERSECT"),
+            SetOperator::Except => write!(f,"EXCEPT"),
+        }
+    }
+}
+
+///queries combined by a set operator, rendered as a derived table named alias
+#[derive(Debug,Clone)]
+pub struct SetOperation {
+    pub operator: SetOperator,
+    pub queries: Vec<QueryBuilder>,
+    pub alias: String,
+}
+
+impl SetOperation {
+    fn to_sql(&self, args: &mut Vec<FieldValue>) -> Result<String, QueryBuildError> {
+        if self.queries.is_empty() {
+            return Err(QueryBuildError::new(BuildErrorType::MissingValues, format!("please provide at least one query for {}", self.operator)));
+        }
+        let mut queries: Vec<String> = vec![];
+        for query in self.queries.iter() {
+            let (query_string, query_args) = query.build()?;
+            queries.push(format!("({})", query_string));
+            args.extend(query_args);
+        }
+        Ok(format!("({}) as {}", queries.join(&format!(" {} ", self.operator)), quote_name(&self.alias)))
+    }
+}
+
+#[derive(Debug,Clone)]
+pub enum LockMode {
+    Update,Share
+}
+
+#[derive(Debug,Clone)]
+pub enum LockWait {
+    Wait,NoWait,SkipLocked
+}
+
+///the FOR UPDATE / FOR SHARE clause of a select
+#[derive(Debug,Clone)]
+pub struct RowLock {
+    pub mode: Option<LockMode>,
+    pub wait: LockWait,
+}
+
+impl RowLock {
+    fn to_sql(&self) -> Result<String, QueryBuildError> {
+        let mode = match &self.mode {
+            Some(LockMode::Update) => "for update",
+            Some(LockMode::Share) => "for share",
+            None => return Err(QueryBuildError::new(BuildErrorType::OtherError, "nowait() and skip_locked() need for_update() or for_share()".to_string())),
+        };
+        match self.wait {
+            LockWait::Wait => Ok(mode.to_string()),
+            LockWait::NoWait => Ok(format!("{} nowait", mode)),
+            LockWait::SkipLocked => Ok(format!("{} skip locked", mode)),
+        }
+    }
+}
+
+///the columns and the source query of INSERT INTO t (columns) SELECT ...
+#[derive(Debug,Clone,Default)]
+pub struct InsertSelect {
+    pub columns: Vec<SelectField>,
+    pub query: Option<Box<QueryBuilder>>,
+    pub update_on_duplicate: bool,
 }
 
 #[derive(Debug,Clone)]
@@ -230,6 +690,8 @@ pub enum SelectField {
     Field(Field),
     Subquery(SubqueryField),
     Untyped(String),
+    ///sql text with `?` placeholders bound to args in order, e.g. a string literal or a CASE
+    Raw { sql: String, args: Vec<FieldValue> },
 }
 
 #[derive(Debug,Clone)]
@@ -238,7 +700,21 @@ pub enum FieldValue {
     String(String),
     Bool(bool),
     I32(i32),
-    F64(f64)
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Decimal(rust_decimal::Decimal),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    Datetime(NaiveDateTime),
+    Bytes(Vec<u8>),
+    ///json text, bound as json where the database has a json type
+    Json(String),
+    ///the value of an enum column, bound without a type so PostgreSQL takes the type of the column
+    Enum(String),
+    ///the value of a uuid column, bound as uuid on PostgreSQL and as text elsewhere
+    Uuid(String),
+    Null,
 }
 
 //帮实现intoString
@@ -251,40 +727,157 @@ impl From<&str> for FieldValue {
     fn from(value: &str) -> Self { FieldValue::String(value.to_string()) }
 }
 
+impl From<&String> for FieldValue {
+    fn from(value: &String) -> Self { FieldValue::String(value.clone()) }
+}
+
 impl From<bool> for FieldValue {
     fn from(value: bool) -> Self { FieldValue::Bool(value) }
 }
 
+impl From<i8> for FieldValue {
+    fn from(value: i8) -> Self { FieldValue::I32(value as i32) }
+}
+
+impl From<i16> for FieldValue {
+    fn from(value: i16) -> Self { FieldValue::I32(value as i32) }
+}
+
 impl From<i32> for FieldValue {
     fn from(value: i32) -> Self { FieldValue::I32(value) }
 }
 
+impl From<i64> for FieldValue {
+    fn from(value: i64) -> Self { FieldValue::I64(value) }
+}
+
+impl From<u32> for FieldValue {
+    fn from(value: u32) -> Self { FieldValue::U64(value as u64) }
+}
+
+impl From<u64> for FieldValue {
+    fn from(value: u64) -> Self { FieldValue::U64(value) }
+}
+
+impl From<f32> for FieldValue {
+    fn from(value: f32) -> Self { FieldValue::F64(value as f64) }
+}
+
 impl From<f64> for FieldValue {
     fn from(value: f64) -> Self { FieldValue::F64(value) }
 }
 
+impl From<rust_decimal::Decimal> for FieldValue {
+    fn from(value: rust_decimal::Decimal) -> Self { FieldValue::Decimal(value) }
+}
+
+impl From<NaiveDate> for FieldValue {
+    fn from(value: NaiveDate) -> Self { FieldValue::Date(value) }
+}
+
+impl From<NaiveTime> for FieldValue {
+    fn from(value: NaiveTime) -> Self { FieldValue::Time(value) }
+}
+
+impl From<NaiveDateTime> for FieldValue {
+    fn from(value: NaiveDateTime) -> Self { FieldValue::Datetime(value) }
+}
+
+impl From<Vec<u8>> for FieldValue {
+    fn from(value: Vec<u8>) -> Self { FieldValue::Bytes(value) }
+}
+
 impl From<Field> for FieldValue {
     fn from(value: Field) -> Self { FieldValue::Field(value) }
 }
 
+impl<T: Into<FieldValue>> From<Option<T>> for FieldValue {
+    fn from(value: Option<T>) -> Self { value.map_or(FieldValue::Null, |v| v.into()) }
+}
+
+impl FieldValue {
+    ///renders the value as a `?` placeholder and collects it into args, columns and null are rendered inline
+    pub(crate) fn to_placeholder(&self, args: &mut Vec<FieldValue>) -> String {
+        match self {
+            FieldValue::Field(f) => f.to_string(),
+            FieldValue::Null => "null".to_string(),
+            _ => {
+                args.push(self.clone());
+                "?".to_string()
+            }
+        }
+    }
+}
+
 impl ToString for FieldValue {
     fn to_string(&self) -> String {
         match self {
             FieldValue::Field(f) => f.to_string(),
-            FieldValue::String(s) => {
+            FieldValue::String(s) | FieldValue::Json(s) | FieldValue::Enum(s) | FieldValue::Uuid(s) => {
                 // 简单转义单引号
                 let escaped = s.replace("'", "''");
                 format!("'{}'", escaped)
             }
-            FieldValue::Bool(b) => if *b { "1".to_string() } else { "0".to_string() },
+            FieldValue::Bool(b) => get_dialect().boolean_literal(*b).to_string(),
             FieldValue::I32(i) => i.to_string(),
+            FieldValue::I64(i) => i.to_string(),
+            FieldValue::U64(i) => i.to_string(),
             FieldValue::F64(f) => {
                 f.to_string()
             }
+            FieldValue::Decimal(d) => d.to_string(),
+            FieldValue::Date(d) => format!("'{}'", d.format("%Y-%m-%d")),
+            FieldValue::Time(t) => format!("'{}'", t.format("%H:%M:%S")),
+            FieldValue::Datetime(dt) => format!("'{}'", dt.format("%Y-%m-%d %H:%M:%S")),
+            FieldValue::Bytes(b) => format!("X'{}'", hex::encode(b)),
+            FieldValue::Null => "null".to_string(),
         }
     }
 }
 
+///sets a column of a row converted to json the way fetch does: `a__b` columns go into the object a, and every column is also set in camel case
+#[cfg(any(feature = "sqlite", feature = "postgres"))]
+pub(crate) fn set_json_column(json_obj: &mut JsonValue, column_name: &str, value: JsonValue) {
+    let (obj_name, column_name) = match column_name.split_once("__") {
+        Some((obj_name, column_name)) => (Some(obj_name), column_name),
+        None => (None, column_name),
+    };
+    let target = match obj_name {
+        Some(obj_name) => {
+            if !json_obj[obj_name].is_object() {
+                json_obj[obj_name] = json!({});
+            }
+            &mut json_obj[obj_name]
+        }
+        None => json_obj,
+    };
+    target[to_camel_case(column_name)] = value.clone();
+    target[column_name] = value;
+}
+
+///collects bind values into MySqlArguments, in the order of the `?` placeholders
+pub(crate) fn to_mysql_arguments(args: &[FieldValue]) -> Result<MySqlArguments, Error> {
+    let mut arguments = MySqlArguments::default();
+    for arg in args {
+        let add_result = match arg {
+            FieldValue::Field(field) => return Err(Error::Encode(format!("column {} can not be bound as a value", field.name).into())),
+            FieldValue::String(value) | FieldValue::Json(value) | FieldValue::Enum(value) | FieldValue::Uuid(value) => arguments.add(value.clone()),
+            FieldValue::Bool(value) => arguments.add(*value),
+            FieldValue::I32(value) => arguments.add(*value),
+            FieldValue::I64(value) => arguments.add(*value),
+            FieldValue::U64(value) => arguments.add(*value),
+            FieldValue::F64(value) => arguments.add(*value),
+            FieldValue::Decimal(value) => arguments.add(*value),
+            FieldValue::Date(value) => arguments.add(*value),
+            FieldValue::Time(value) => arguments.add(*value),
+            FieldValue::Datetime(value) => arguments.add(*value),
+            FieldValue::Bytes(value) => arguments.add(value.clone()),
+            FieldValue::Null => arguments.add(None::<String>),
+        };
+        add_result.map_err(Error::Encode)?;
+    }
+    Ok(arguments)
+}
 
 impl SelectField {
     pub fn target(&self, target: &str) -> Self {
@@ -295,6 +888,7 @@ impl SelectField {
                 target: Some(target.to_string()),
                 as_: subquery_field.as_.clone(),
             }),
+            SelectField::Raw { .. } => self.clone(),
             SelectField::Untyped(String) => self.clone(), //target for String is not support, pls write it directly as SelectField::Untyped("'xxx' as aaa__bbb".to_string()) where aaa is target
         }
     }
@@ -303,7 +897,7 @@ impl SelectField {
         match &mut self {
             SelectField::Field(field) => field.as_ = None,
             SelectField::Subquery(subquery_field) => subquery_field.as_ = None,
-            SelectField::Untyped(_) => {}
+            SelectField::Untyped(_) | SelectField::Raw { .. } => {}
         }
         self
     }
@@ -382,10 +976,11 @@ impl From<&Varchar> for SelectField{
                 SelectField::Field(Field::new(&varchar.table(),&varchar.name(), None, varchar.alias(),varchar.is_encrypted()))
             },
             Holding::Value => {
+                let args = vec![FieldValue::String(varchar.value().unwrap_or_default())];
                 if varchar.alias().is_some() {
-                    SelectField::Untyped(format!("'{}' as {}",&varchar.value().unwrap_or_default(), varchar.alias().unwrap()))
+                    SelectField::Raw{sql: format!("? as {}", quote_name(&varchar.alias().unwrap())), args}
                 }else {
-                    SelectField::Untyped(format!("'{}'",&varchar.value().unwrap_or_default()))
+                    SelectField::Raw{sql: "?".to_string(), args}
                 }
             },
             Holding::NameValue=> {
@@ -632,11 +1227,6 @@ impl <T:Clone+Into<String>> From<&Set<T>> for SelectField{
     }
 }
 
-fn encrypt_value(value:String)->String{
-    let encryptor = get_encryptor();
-    encryptor.encrypt(value)
-}
-
 fn decrypt_field(field:Field) -> String {
     let encryptor = get_encryptor();
     encryptor.decrypt_field(field)
@@ -644,18 +1234,26 @@ fn decrypt_field(field:Field) -> String {
 
 impl ToString for Field {
     fn to_string(&self) -> String {
+        //asc()/desc() append the direction to the name, only the column itself is quoted
+        let (name, direction) = match self.name.rsplit_once(' ') {
+            Some((name, direction)) if direction == "asc" || direction == "desc" => (name, format!(" {}", direction)),
+            _ => (self.name.as_str(), String::new()),
+        };
         let mut qualified_field = if self.table.is_empty() {
-            self.name.clone()
+            format!("{}{}", quote_name(name), direction)
         }else {
-            format!("{}.{}", self.table, self.name)
+            format!("{}{}", qualify_name(&self.table, name), direction)
         };
-        let mut alias = self.as_.clone();
+        let mut alias = self.as_.clone().map(|alias| quote_name(&alias));
         if self.is_encrypted {
             //这里如果是用于select的话，需要解密，如果是用于其它地方如Where ... 的话，需要加密。当前仅按select来处理.
             //如果要区分用途，需要在Field中增加一个context字段表示用途，如context:Select,Where ... ，目前先不增加
-            qualified_field = decrypt_field(self.clone());
+            let mut quoted_field = self.clone();
+            quoted_field.table = if self.table.is_empty() {String::new()} else {self.table.split('.').map(quote_name).collect::<Vec<String>>().join(".")};
+            quoted_field.name = quote_name(&self.name);
+            qualified_field = decrypt_field(quoted_field);
             if alias.is_none(){
-                alias = Some(self.name.clone())
+                alias = Some(quote_name(&self.name))
             }
         }
 
@@ -665,9 +1263,9 @@ impl ToString for Field {
                 target = target.replace(".","__");
             }
             if alias.is_some() {
-                qualified_field = format!("{} AS {}__{}", qualified_field, &target, alias.unwrap());
+                qualified_field = format!("{} AS {}", qualified_field, quote_name(&format!("{}__{}", &target, self.as_.clone().unwrap_or(self.name.clone()))));
             }else{
-                qualified_field = format!("{} AS {}__{}", qualified_field, &target, self.name);
+                qualified_field = format!("{} AS {}", qualified_field, quote_name(&format!("{}__{}", &target, self.name)));
             }
         }else if alias.is_some() {
             qualified_field = format!("{} AS {}", qualified_field, alias.unwrap());
@@ -679,8 +1277,16 @@ impl ToString for Field {
 
 impl ToString for SubqueryField {
     fn to_string(&self) -> String {
-        if let Ok(build_result) = self.query_builder.build() {
-            if self.as_.is_some() {format!("({}) AS {}", build_result,self.as_.clone().unwrap_or_default())} else {build_result}
+        self.to_sql(&mut vec![])
+    }
+}
+
+impl SubqueryField {
+    ///renders the subquery and collects its bind values into args
+    pub(crate) fn to_sql(&self, args: &mut Vec<FieldValue>) -> String {
+        if let Ok((build_result, sub_args)) = self.query_builder.build() {
+            args.extend(sub_args);
+            if self.as_.is_some() {format!("({}) AS {}", build_result,quote_name(&self.as_.clone().unwrap_or_default()))} else {build_result}
         }else {
             "[wrong subquery statement]".to_string()
         }
@@ -693,15 +1299,104 @@ impl ToString for SelectField {
             SelectField::Field(field) => field.to_string(),
             SelectField::Subquery(subquery) => subquery.to_string(),
             SelectField::Untyped(s) => s.clone(),
+            SelectField::Raw { sql, .. } => sql.clone(),
         }
     }
 }
 
+impl SelectField {
+    ///the column name this field gets in the result set, i.e. its alias if it has one
+    pub(crate) fn output_name(&self) -> String {
+        match self {
+            SelectField::Field(field) => {
+                let name = field.as_.clone().unwrap_or(field.name.clone());
+                match &field.target {
+                    Some(target) => format!("{}__{}", target.replace(".", "__"), name),
+                    None => name,
+                }
+            }
+            SelectField::Subquery(subquery) => subquery.as_.clone().unwrap_or(subquery.to_string()),
+            SelectField::Untyped(s) | SelectField::Raw { sql: s, .. } => {
+                let lower = s.to_lowercase();
+                match lower.rfind(" as ") {
+                    Some(index) => s[index + 4..].trim().to_string(),
+                    None => s.trim().to_string(),
+                }
+            }
+        }
+    }
 
+    ///like to_string, but keeps the bind values of subqueries in args
+    pub(crate) fn to_sql(&self, args: &mut Vec<FieldValue>) -> String {
+        match self {
+            SelectField::Subquery(subquery) => subquery.to_sql(args),
+            SelectField::Raw { sql, args: raw_args } => {
+                args.extend(raw_args.iter().cloned());
+                sql.clone()
+            }
+            _ => self.to_string(),
+        }
+    }
+}
+
+
+
+///the OVER (...) clause of a window function, e.g. `partition_by(vec![t.user_id()]).order_by(vec![t.created_at().desc()])`
+#[derive(Debug,Clone,Default)]
+pub struct Window {
+    partition_by: Vec<SelectField>,
+    order_by: Vec<SelectField>,
+}
+
+impl Window {
+    pub fn new() -> Self {
+        Window::default()
+    }
+
+    pub fn partition_by<T: Into<SelectField>>(mut self, fields: Vec<T>) -> Self {
+        self.partition_by.extend(fields.into_iter().map(|field| field.into().unset_as()));
+        self
+    }
+
+    pub fn order_by<T: Into<SelectField>>(mut self, fields: Vec<T>) -> Self {
+        self.order_by.extend(fields.into_iter().map(|field| field.into().unset_as()));
+        self
+    }
+}
+
+impl ToString for Window {
+    fn to_string(&self) -> String {
+        let mut clauses = vec![];
+        if !self.partition_by.is_empty() {
+            clauses.push(format!("PARTITION BY {}", self.partition_by.iter().map(|field| field.to_string()).collect::<Vec<String>>().join(", ")));
+        }
+        if !self.order_by.is_empty() {
+            clauses.push(format!("ORDER BY {}", self.order_by.iter().map(|field| field.to_string()).collect::<Vec<String>>().join(", ")));
+        }
+        clauses.join(" ")
+    }
+}
+
+///a window function waiting for its OVER clause, over() turns it into a typed column
+#[derive(Debug,Clone)]
+pub struct WindowFunction<T> {
+    function: String,
+    to_column: fn(String) -> T,
+}
+
+impl<T> WindowFunction<T> {
+    pub fn new(function: String, to_column: fn(String) -> T) -> Self {
+        WindowFunction { function, to_column }
+    }
+
+    pub fn over(self, window: Window) -> T {
+        (self.to_column)(format!("{} OVER ({})", self.function, window.to_string()))
+    }
+}
 
 #[derive(Debug,Clone)]
 pub struct QueryBuilder {
-    operation:Operation,
+    pub(crate) operation:Operation,
     is_select_all:Option<bool>,
     distinct:Option<bool>,
     count_all:Option<bool>,
@@ -714,59 +1409,32 @@ pub struct QueryBuilder {
     limit:Option<Limit>,
     order_by:Vec<SelectField>,
     group_by:Vec<SelectField>,
+    having:Vec<Condition>,
     update_values: Vec<(SelectField, FieldValue)>, // 用于存储更新字段和值
+    set_operation: Option<SetOperation>,//selects from the combined result of union/intersect/except instead of target_table
+    insert_select: Option<InsertSelect>,
+    lock: Option<RowLock>,
+    hints: Vec<OptimizerHint>,
+    returning: Vec<SelectField>,
 }
 
-/// 包装字段名，处理SQL关键字
-fn wrap_field_name(field_name: &str) -> String {
-    // SQL 关键字列表
-    let sql_keywords = [
-        "order", "group", "table", "select", "insert", "update", "delete", "where",
-        "from", "as", "and", "or", "not", "null", "is", "in", "like", "between",
-        "join", "left", "right", "inner", "outer", "on", "by", "having", "distinct",
-        "limit", "offset", "set", "values", "into", "create", "alter", "drop",
-        "index", "primary", "key", "foreign", "references", "constraint", "default",
-        "auto_increment", "desc", "asc", "union", "all", "case", "when", "then",
-        "else", "end", "exists", "any", "some", "user", "database", "schema",
-        "view", "procedure", "function", "trigger", "event", "temporary", "if",
-        "elseif", "begin", "end", "declare", "set", "call", "execute", "show",
-        "describe", "explain", "use", "grant", "revoke", "privileges", "flush",
-        "lock", "unlock", "commit", "rollback", "savepoint", "transaction",
-        "character", "collate", "engine", "row_format", "comment", "partition",
-        "check", "cascade", "restrict", "no", "action", "match", "full", "natural",
-        "cross", "using", "current_date", "current_time", "current_timestamp",
-        "interval", "year", "month", "day", "hour", "minute", "second", "microsecond",
-        "date", "time", "timestamp", "datetime", "year", "char", "varchar", "text",
-        "tinytext", "mediumtext", "longtext", "blob", "tinyblob", "mediumblob",
-        "longblob", "enum", "set", "decimal", "numeric", "float", "double", "real",
-        "bit", "bool", "boolean", "serial", "bigserial", "smallserial", "money",
-        "uuid", "json", "jsonb", "xml", "array", "range", "multirange", "domain"
-    ];
-
-    // 转换为小写比较
-    let lower_name = field_name.to_lowercase();
-
-    // 使用迭代器进行查找
-    if sql_keywords.iter().any(|&kw| kw == lower_name) {
-        format!("`{}`", field_name)
-    } else {
-        field_name.to_string()
-    }
-}
-fn add_text_upsert_fields_values(name:String, value:Option<String>, insert_fields: &mut Vec<String>, insert_values: &mut Vec<String>, update_fields_values: &mut Vec<String>, is_encrypted:bool){
-    let wrapped_name = wrap_field_name(&name);
+fn add_text_upsert_fields_values(name:String, value:Option<String>, insert_fields: &mut Vec<String>, insert_values: &mut Vec<String>, insert_args: &mut Vec<FieldValue>, update_fields_values: &mut Vec<String>, update_args: &mut Vec<FieldValue>, is_encrypted:bool){
+    let wrapped_name = quote_name(&name);
     if(!insert_fields.contains(&wrapped_name)){
         //update_fields_values.push(format!("{} = VALUES({})", &name, &name));
         insert_fields.push(wrapped_name.clone());
         if let Some(string_value) = value {
             if is_encrypted {
-                insert_values.push(format!("{}", encrypt_value(string_value.clone())));
-                update_fields_values.push(format!("{} = {}", &wrapped_name.clone(), &encrypt_value(string_value)));
+                //the plaintext is bound to the placeholder of the encryptor expression
+                insert_values.push(encrypt_bound());
+                insert_args.push(FieldValue::String(string_value.clone()));
+                update_fields_values.push(format!("{} = {}", &wrapped_name.clone(), encrypt_bound()));
+                update_args.push(FieldValue::String(string_value));
             }else{
-                // 处理单引号转义
-                let escaped_value = string_value.replace("'", "''");
-                insert_values.push(format!("'{}'", escaped_value.clone()));
-                update_fields_values.push(format!("{} = '{}'", &wrapped_name.clone(), &escaped_value));
+                insert_values.push("?".to_string());
+                insert_args.push(FieldValue::String(string_value.clone()));
+                update_fields_values.push(format!("{} = ?", &wrapped_name.clone()));
+                update_args.push(FieldValue::String(string_value));
             }
         }else{
             insert_values.push("null".to_string());
@@ -775,194 +1443,211 @@ fn add_text_upsert_fields_values(name:String, value:Option<String>, insert_field
     }
 }
 
-fn add_non_text_upsert_fields_values(name:String, value:Option<String>, insert_fields: &mut Vec<String>, insert_values: &mut Vec<String>,update_fields_values: &mut Vec<String>){
-    let wrapped_name = wrap_field_name(&name);
+fn add_non_text_upsert_fields_values(name:String, value:Option<FieldValue>, insert_fields: &mut Vec<String>, insert_values: &mut Vec<String>, insert_args: &mut Vec<FieldValue>, update_fields_values: &mut Vec<String>, update_args: &mut Vec<FieldValue>){
+    let wrapped_name = quote_name(&name);
     //update_fields_values.push(format!("{} = VALUES('{}')", &name, &name));
     insert_fields.push(wrapped_name.clone());
-    if let Some(string_value) = value {
-        insert_values.push(format!("{}", string_value));
-        update_fields_values.push(format!("{} = {}", &wrapped_name.clone(), &string_value));
+    if let Some(value) = value {
+        insert_values.push(value.to_placeholder(insert_args));
+        update_fields_values.push(format!("{} = {}", &wrapped_name.clone(), value.to_placeholder(update_args)));
     }else{
         insert_values.push("null".to_string());
         update_fields_values.push(format!("{} = null", &wrapped_name));
     }
 }
 
-fn construct_upsert_fields_values(columns:&Vec<SqlColumn>, insert_fields: &mut Vec<String>, insert_values: &mut Vec<String>,update_fields_values: &mut Vec<String>,skip_field_names:Vec<String>){
+//date, time, json and enum columns are bound with their own type, which PostgreSQL needs to store them, and as text when encrypted
+fn add_typed_upsert_fields_values(name:String, text:Option<String>, value:Option<FieldValue>, insert_fields: &mut Vec<String>, insert_values: &mut Vec<String>, insert_args: &mut Vec<FieldValue>, update_fields_values: &mut Vec<String>, update_args: &mut Vec<FieldValue>, is_encrypted:bool){
+    if is_encrypted {
+        add_text_upsert_fields_values(name,text,insert_fields,insert_values,insert_args,update_fields_values,update_args,true);
+    }else if !insert_fields.contains(&quote_name(&name)) {
+        add_non_text_upsert_fields_values(name,value,insert_fields,insert_values,insert_args,update_fields_values,update_args);
+    }
+}
+
+fn construct_upsert_fields_values(columns:&Vec<SqlColumn>, insert_fields: &mut Vec<String>, insert_values: &mut Vec<String>, insert_args: &mut Vec<FieldValue>, update_fields_values: &mut Vec<String>, update_args: &mut Vec<FieldValue>, skip_field_names:Vec<String>){
     for column_def in columns {
         match column_def {
             SqlColumn::Varchar(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_text_upsert_fields_values(col.name(),col.value(),insert_fields,insert_values,update_fields_values,col.is_encrypted());
+                        if col.is_enum() {
+                            add_typed_upsert_fields_values(col.name(),col.value(),col.value().map(FieldValue::Enum),insert_fields,insert_values,insert_args,update_fields_values,update_args,col.is_encrypted());
+                        }else {
+                            add_text_upsert_fields_values(col.name(),col.value(),insert_fields,insert_values,insert_args,update_fields_values,update_args,col.is_encrypted());
+                        }
                     }
                 }
             }
             SqlColumn::Char(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_text_upsert_fields_values(col.name(),col.value(),insert_fields,insert_values,update_fields_values,col.is_encrypted());
+                        if col.is_uuid() {
+                            add_typed_upsert_fields_values(col.name(),col.value(),col.value().map(FieldValue::Uuid),insert_fields,insert_values,insert_args,update_fields_values,update_args,col.is_encrypted());
+                        }else {
+                            add_text_upsert_fields_values(col.name(),col.value(),insert_fields,insert_values,insert_args,update_fields_values,update_args,col.is_encrypted());
+                        }
                     }
                 }
             }
             SqlColumn::Tinytext(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_text_upsert_fields_values(col.name(),col.value(),insert_fields,insert_values,update_fields_values,col.is_encrypted());
+                        add_text_upsert_fields_values(col.name(),col.value(),insert_fields,insert_values,insert_args,update_fields_values,update_args,col.is_encrypted());
                     }
                 }
             }
             SqlColumn::Text(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_text_upsert_fields_values(col.name(),col.value(),insert_fields,insert_values,update_fields_values,col.is_encrypted());
+                        add_text_upsert_fields_values(col.name(),col.value(),insert_fields,insert_values,insert_args,update_fields_values,update_args,col.is_encrypted());
                     }
                 }
             }
             SqlColumn::Mediumtext(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_text_upsert_fields_values(col.name(),col.value(),insert_fields,insert_values,update_fields_values,col.is_encrypted());
+                        add_text_upsert_fields_values(col.name(),col.value(),insert_fields,insert_values,insert_args,update_fields_values,update_args,col.is_encrypted());
                     }
                 }
             }
             SqlColumn::Longtext(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_text_upsert_fields_values(col.name(),col.value(),insert_fields,insert_values,update_fields_values,col.is_encrypted());
+                        add_text_upsert_fields_values(col.name(),col.value(),insert_fields,insert_values,insert_args,update_fields_values,update_args,col.is_encrypted());
                     }
                 }
             }
             SqlColumn::Enum(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_text_upsert_fields_values(col.name(),col.value_as_string(),insert_fields,insert_values,update_fields_values,col.is_encrypted());
+                        add_typed_upsert_fields_values(col.name(),col.value_as_string(),col.value_as_string().map(FieldValue::Enum),insert_fields,insert_values,insert_args,update_fields_values,update_args,col.is_encrypted());
                     }
                 }
             }
             SqlColumn::Set(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_text_upsert_fields_values(col.name(),col.value_as_string(),insert_fields,insert_values,update_fields_values,col.is_encrypted());
+                        add_text_upsert_fields_values(col.name(),col.value_as_string(),insert_fields,insert_values,insert_args,update_fields_values,update_args,col.is_encrypted());
                     }
                 }
             }
             SqlColumn::Boolean(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_non_text_upsert_fields_values(col.name(),if let Some(value) = col.value() { if value {Some("1".to_string())} else {Some("0".to_string())}} else {None},insert_fields,insert_values,update_fields_values);
+                        add_non_text_upsert_fields_values(col.name(),col.value().map(FieldValue::Bool),insert_fields,insert_values,insert_args,update_fields_values,update_args);
                     }
                 }
             }
             SqlColumn::Tinyint(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_non_text_upsert_fields_values(col.name(),if let Some(value) = col.value() { Some(value.to_string())} else {None},insert_fields,insert_values,update_fields_values);
+                        add_non_text_upsert_fields_values(col.name(),col.value().map(FieldValue::from),insert_fields,insert_values,insert_args,update_fields_values,update_args);
                     }
                 }
             }
             SqlColumn::Smallint(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_non_text_upsert_fields_values(col.name(),if let Some(value) = col.value() { Some(value.to_string())} else {None},insert_fields,insert_values,update_fields_values);
+                        add_non_text_upsert_fields_values(col.name(),col.value().map(FieldValue::from),insert_fields,insert_values,insert_args,update_fields_values,update_args);
                     }
                 }
             }
             SqlColumn::Int(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_non_text_upsert_fields_values(col.name(),if let Some(value) = col.value() { Some(value.to_string())} else {None},insert_fields,insert_values,update_fields_values);
+                        add_non_text_upsert_fields_values(col.name(),col.value().map(FieldValue::from),insert_fields,insert_values,insert_args,update_fields_values,update_args);
                     }
                 }
             }
             SqlColumn::Bigint(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_non_text_upsert_fields_values(col.name(),if let Some(value) = col.value() { Some(value.to_string())} else {None},insert_fields,insert_values,update_fields_values);
+                        add_non_text_upsert_fields_values(col.name(),col.value().map(FieldValue::from),insert_fields,insert_values,insert_args,update_fields_values,update_args);
                     }
                 }
             }
             SqlColumn::BigintUnsigned(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_non_text_upsert_fields_values(col.name(),if let Some(value) = col.value() { Some(value.to_string())} else {None},insert_fields,insert_values,update_fields_values);
+                        add_non_text_upsert_fields_values(col.name(),col.value().map(FieldValue::from),insert_fields,insert_values,insert_args,update_fields_values,update_args);
                     }
                 }
             }
             SqlColumn::Numeric(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_non_text_upsert_fields_values(col.name(),if let Some(value) = col.value() { Some(value.to_string())} else {None},insert_fields,insert_values,update_fields_values);
+                        add_non_text_upsert_fields_values(col.name(),col.value().map(FieldValue::from),insert_fields,insert_values,insert_args,update_fields_values,update_args);
                     }
                 }
             }
             SqlColumn::Float(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_non_text_upsert_fields_values(col.name(),if let Some(value) = col.value() { Some(value.to_string())} else {None},insert_fields,insert_values,update_fields_values);
+                        add_non_text_upsert_fields_values(col.name(),col.value().map(FieldValue::from),insert_fields,insert_values,insert_args,update_fields_values,update_args);
                     }
                 }
             }
             SqlColumn::Double(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_non_text_upsert_fields_values(col.name(),if let Some(value) = col.value() { Some(value.to_string())} else {None},insert_fields,insert_values,update_fields_values);
+                        add_non_text_upsert_fields_values(col.name(),col.value().map(FieldValue::from),insert_fields,insert_values,insert_args,update_fields_values,update_args);
                     }
                 }
             }
             SqlColumn::Decimal(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_non_text_upsert_fields_values(col.name(),if let Some(value) = col.value() { Some(value.to_string())} else {None},insert_fields,insert_values,update_fields_values);
+                        add_non_text_upsert_fields_values(col.name(),col.value().map(FieldValue::from),insert_fields,insert_values,insert_args,update_fields_values,update_args);
                     }
                 }
             }
             SqlColumn::Date(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_text_upsert_fields_values(col.name(),if let Some(value) = col.value() { Some(value.format("%Y-%m-%d").to_string())} else {None},insert_fields,insert_values,update_fields_values,col.is_encrypted());
+                        add_typed_upsert_fields_values(col.name(),col.value().map(|value| value.format("%Y-%m-%d").to_string()),col.value().map(FieldValue::Date),insert_fields,insert_values,insert_args,update_fields_values,update_args,col.is_encrypted());
                     }
                 }
             }
             SqlColumn::Time(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_text_upsert_fields_values(col.name(),if let Some(value) = col.value() { Some(value.format("%H:%M:%S").to_string())} else {None},insert_fields,insert_values,update_fields_values,col.is_encrypted());
+                        add_typed_upsert_fields_values(col.name(),col.value().map(|value| value.format("%H:%M:%S").to_string()),col.value().map(FieldValue::Time),insert_fields,insert_values,insert_args,update_fields_values,update_args,col.is_encrypted());
                     }
                 }
             }
             SqlColumn::Datetime(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_text_upsert_fields_values(col.name(),if let Some(value) = col.value() { Some(value.format("%Y-%m-%d %H:%M:%S").to_string())} else {None},insert_fields,insert_values,update_fields_values,col.is_encrypted());
+                        add_typed_upsert_fields_values(col.name(),col.value().map(|value| value.format("%Y-%m-%d %H:%M:%S").to_string()),col.value().map(|value| FieldValue::Datetime(value.naive_local())),insert_fields,insert_values,insert_args,update_fields_values,update_args,col.is_encrypted());
                     }
                 }
             }
             SqlColumn::Timestamp(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_text_upsert_fields_values(col.name(),if let Some(value) = col.value() { Some(value.format("%Y-%m-%d %H:%M:%S").to_string())} else {None},insert_fields,insert_values,update_fields_values,col.is_encrypted());
+                        add_typed_upsert_fields_values(col.name(),col.value().map(|value| value.format("%Y-%m-%d %H:%M:%S").to_string()),col.value().map(|value| FieldValue::Datetime(value.naive_local())),insert_fields,insert_values,insert_args,update_fields_values,update_args,col.is_encrypted());
                     }
                 }
             }
             SqlColumn::Year(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_non_text_upsert_fields_values(col.name(),if let Some(value) = col.value() { Some(value.to_string())} else {None},insert_fields,insert_values,update_fields_values);
+                        add_non_text_upsert_fields_values(col.name(),col.value().map(FieldValue::from),insert_fields,insert_values,insert_args,update_fields_values,update_args);
                     }
                 }
             }
-            SqlColumn::Blob(column_def) => {//todo:how?
+            SqlColumn::Blob(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_non_text_upsert_fields_values(col.name(),if let Some(value) = col.value() { if let Ok(string) = String::from_utf8(value.clone()) {Some(string)} else {None} } else {None},insert_fields,insert_values,update_fields_values);
+                        add_non_text_upsert_fields_values(col.name(),col.value().map(FieldValue::Bytes),insert_fields,insert_values,insert_args,update_fields_values,update_args);
                     }
                 }
             }
             SqlColumn::Json(column_def) => {
                 if let Some(col) = column_def {
                     if !skip_field_names.contains(&col.name()) {
-                        add_text_upsert_fields_values(col.name(), if let Some(value) = col.value() { Some(value.to_string()) } else { None }, insert_fields, insert_values, update_fields_values,col.is_encrypted());
+                        add_typed_upsert_fields_values(col.name(), col.value(), col.value().map(FieldValue::Json), insert_fields, insert_values, insert_args, update_fields_values, update_args,col.is_encrypted());
                     }
                 }
             }
@@ -970,7 +1655,263 @@ fn construct_upsert_fields_values(columns:&Vec<SqlColumn>, insert_fields: &mut V
     }
 }
 
-pub fn construct_upsert_primary_key_value(columns:&Vec<SqlColumn>, insert_fields: &mut Vec<String>, insert_values: &mut Vec<String>, primary_key_as_conditions: &mut Vec<String>) {
+///the opaque cursor of fetch_after: the order_by values of the row it points at, and whether it asks for the page before it
+#[derive(Debug, Serialize, Deserialize)]
+struct PageCursor {
+    before: bool,
+    values: Vec<CursorValue>,
+}
+
+impl PageCursor {
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    fn decode(cursor: &str) -> Result<PageCursor, Error> {
+        URL_SAFE_NO_PAD.decode(cursor).ok()
+            .and_then(|bytes| serde_json::from_slice::<PageCursor>(&bytes).ok())
+            .ok_or_else(|| Error::Configuration(format!("invalid cursor {}", cursor).into()))
+    }
+}
+
+///an order_by value of a cursor, typed by its column so it is bound back exactly as it was read, e.g. a datetime keeps its fraction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum CursorValue {
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    //as text, json numbers would round it
+    Decimal(String),
+    String(String),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    Datetime(NaiveDateTime),
+}
+
+impl CursorValue {
+    ///the value of column index of row, read by its MySQL type
+    fn of(row: &MySqlRow, index: usize) -> Result<CursorValue, Error> {
+        let column = &row.columns()[index];
+        if row.try_get_raw(index)?.is_null() {
+            return Err(Error::Configuration(format!("fetch_after does not support null values in order_by column {}", column.name()).into()));
+        }
+        let type_name = column.type_info().name();
+        Ok(match type_name {
+            "BOOLEAN" => CursorValue::Bool(row.try_get(index)?),
+            "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "BIGINT" | "YEAR" => CursorValue::I64(row.try_get(index)?),
+            _ if type_name.ends_with(" UNSIGNED") => CursorValue::U64(row.try_get(index)?),
+            "FLOAT" | "DOUBLE" => CursorValue::F64(row.try_get(index)?),
+            "DECIMAL" => CursorValue::Decimal(row.try_get::<rust_decimal::Decimal, _>(index)?.to_string()),
+            "DATE" => CursorValue::Date(row.try_get(index)?),
+            "TIME" => CursorValue::Time(row.try_get(index)?),
+            "DATETIME" | "TIMESTAMP" => CursorValue::Datetime(row.try_get(index)?),
+            "JSON" | "BLOB" | "TINYBLOB" | "MEDIUMBLOB" | "LONGBLOB" | "BINARY" | "VARBINARY" => {
+                return Err(Error::Configuration(format!("fetch_after does not support {} values in order_by column {}", type_name, column.name()).into()));
+            }
+            _ => CursorValue::String(row.try_get(index)?),
+        })
+    }
+
+    fn to_field_value(&self) -> Result<FieldValue, Error> {
+        Ok(match self {
+            CursorValue::Bool(value) => FieldValue::Bool(*value),
+            CursorValue::I64(value) => FieldValue::I64(*value),
+            CursorValue::U64(value) => FieldValue::U64(*value),
+            CursorValue::F64(value) => FieldValue::F64(*value),
+            CursorValue::Decimal(value) => FieldValue::Decimal(value.parse().map_err(|_| Error::Configuration(format!("invalid decimal {} in cursor", value).into()))?),
+            CursorValue::String(value) => FieldValue::String(value.clone()),
+            CursorValue::Date(value) => FieldValue::Date(*value),
+            CursorValue::Time(value) => FieldValue::Time(*value),
+            CursorValue::Datetime(value) => FieldValue::Datetime(*value),
+        })
+    }
+}
+
+///an order_by field of fetch_after: the sorted expression, its direction and the name of its column in the result
+struct SeekKey {
+    expression: String,
+    descending: bool,
+    column: String,
+}
+
+///the keys of order_by, named by the select field they are selected as, e.g. `order.created_at` selected `as createdAt`
+fn seek_keys(order_by: &Vec<SelectField>, select_fields: &Vec<SelectField>) -> Vec<SeekKey> {
+    let unquote = |name: &str| name.trim().replace(['`', '"'], "");
+    order_by.iter().map(|field| {
+        let mut expression = match field {
+            SelectField::Field(field) => if field.table.is_empty() { field.name.clone() } else { format!("{}.{}", field.table, field.name) },
+            _ => field.to_string(),
+        }.trim().to_string();
+        let lower = expression.to_lowercase();
+        let mut descending = false;
+        if lower.ends_with(" desc") {
+            descending = true;
+            expression.truncate(expression.len() - 5);
+        }else if lower.ends_with(" asc") {
+            expression.truncate(expression.len() - 4);
+        }
+        let mut expression = expression.trim().to_string();
+        let column = match field {
+            SelectField::Field(field) => {
+                let name = unquote(expression.rsplit('.').next().unwrap_or_default());
+                expression = if field.table.is_empty() { quote_name(&name) } else { qualify_name(&field.table, &name) };
+                let selected = select_fields.iter().find(|selected| match selected {
+                    SelectField::Field(selected) => unquote(&selected.name) == name
+                        && (field.table.is_empty() || selected.table.is_empty() || unquote(&selected.table) == unquote(&field.table)),
+                    _ => false,
+                });
+                selected.map_or(name, |selected| selected.output_name())
+            }
+            _ => {
+                //an expression selected with an alias, or the alias itself
+                let selected = select_fields.iter().find(|selected| (*selected).clone().unset_as().to_string().trim() == expression);
+                selected.map_or_else(|| unquote(expression.rsplit('.').next().unwrap_or_default()), |selected| unquote(&selected.output_name()))
+            }
+        };
+        SeekKey { expression, descending, column }
+    }).collect()
+}
+
+///rows after the cursor values in the order of keys, or before them. `(a, b) > (?, ?)` when all keys sort the same way,
+///`(a > ?) OR (a = ? AND b < ?)` otherwise
+fn seek_condition(keys: &Vec<SeekKey>, values: Vec<FieldValue>, before: bool) -> Condition {
+    let operator = |key: &SeekKey| if key.descending == before { ">" } else { "<" };
+    if keys.iter().all(|key| key.descending == keys[0].descending) {
+        return Condition::with_args(format!("({}) {} ({})",
+            keys.iter().map(|key| key.expression.clone()).collect::<Vec<String>>().join(", "),
+            operator(&keys[0]),
+            keys.iter().map(|_| "?").collect::<Vec<&str>>().join(", ")), values);
+    }
+    let mut alternatives: Vec<Condition> = vec![];
+    for i in 0..keys.len() {
+        let mut parts: Vec<Condition> = vec![];
+        for j in 0..i {
+            parts.push(Condition::compare(keys[j].expression.clone(), "=", values[j].clone()));
+        }
+        parts.push(Condition::compare(keys[i].expression.clone(), operator(&keys[i]), values[i].clone()));
+        alternatives.push(Condition::And(parts));
+    }
+    Condition::Or(alternatives)
+}
+
+///a page of fetch_after from its rows and their cursor values, fetched with one row more than page_size
+fn cursor_page<T: for<'de> serde::Deserialize<'de>>(mut rows: Vec<(JsonValue, Vec<CursorValue>)>, page_size: i32, before: bool, has_cursor: bool) -> Result<CursorPagingData<T>, Error> {
+    let has_more = rows.len() > page_size as usize;
+    rows.truncate(page_size as usize);
+    if before {
+        rows.reverse();
+    }
+    let cursor_of = |values: &Vec<CursorValue>, before: bool| PageCursor { before, values: values.clone() }.encode();
+    let first_cursor = rows.first().map(|(_, values)| cursor_of(values, true));
+    let last_cursor = rows.last().map(|(_, values)| cursor_of(values, false));
+    let (next_cursor, prev_cursor) = if before {
+        (last_cursor, if has_more { first_cursor } else { None })
+    }else {
+        (if has_more { last_cursor } else { None }, if has_cursor { first_cursor } else { None })
+    };
+
+    let mut result = Vec::new();
+    for (json, _) in rows {
+        let item_parsed = serde_json::from_value::<T>(json).map_err(|err| Error::Configuration(err.into()))?;
+        result.push(item_parsed);
+    }
+    Ok(CursorPagingData::new(result, page_size, next_cursor, prev_cursor))
+}
+
+///groups column types whose values can be copied into each other by insert_into
+fn column_type_family(column: &SqlColumn) -> &'static str {
+    match column {
+        SqlColumn::Char(_) | SqlColumn::Varchar(_) | SqlColumn::Tinytext(_) | SqlColumn::Text(_) | SqlColumn::Mediumtext(_) | SqlColumn::Longtext(_) | SqlColumn::Enum(_) | SqlColumn::Set(_) => "text",
+        SqlColumn::Boolean(_) | SqlColumn::Tinyint(_) | SqlColumn::Smallint(_) | SqlColumn::Int(_) | SqlColumn::Bigint(_) | SqlColumn::BigintUnsigned(_) | SqlColumn::Year(_) => "integer",
+        SqlColumn::Numeric(_) | SqlColumn::Float(_) | SqlColumn::Double(_) | SqlColumn::Decimal(_) => "decimal",
+        SqlColumn::Date(_) | SqlColumn::Time(_) | SqlColumn::Datetime(_) | SqlColumn::Timestamp(_) => "temporal",
+        SqlColumn::Blob(_) => "binary",
+        SqlColumn::Json(_) => "json",
+    }
+}
+
+///placeholders a MySQL prepared statement accepts at most
+const MAX_PLACEHOLDERS: usize = 65535;
+
+///bytes a bound value roughly takes in the packet sent to the server
+fn estimated_size(value: &FieldValue) -> usize {
+    match value {
+        FieldValue::String(value) | FieldValue::Json(value) | FieldValue::Enum(value) | FieldValue::Uuid(value) => value.len() + 9,
+        FieldValue::Bytes(value) => value.len() + 9,
+        _ => 9,
+    }
+}
+
+///builds the multi-row INSERT statements of insert_many, every row must set the same columns.
+///rows are split into statements of at most max_packet_size bytes and 65535 placeholders,
+///with update_on_duplicate the non primary key columns are updated by the upsert clause of the dialect, ON DUPLICATE KEY UPDATE col = VALUES(col) on MySQL
+pub fn build_insert_many<A>(rows: &[A], update_on_duplicate: bool, max_packet_size: usize) -> Result<Vec<(String, Vec<FieldValue>)>, QueryBuildError> where A: Table {
+    let mut fields: Option<Vec<String>> = None;
+    let mut primary_key_names: Vec<String> = vec![];
+    let mut table_name = "".to_string();
+    let mut values_list: Vec<(String, Vec<FieldValue>)> = vec![];
+    for row in rows {
+        let target_table = TargetTable::new(row);
+        if target_table.primary_key.is_empty() {
+            return Err(QueryBuildError::new(BuildErrorType::MissingPrimaryKey, "Primary key not found for insert_many operation".to_string()));
+        }
+        primary_key_names = target_table.primary_key.iter().map(|it|quote_name(&it.get_col_name())).collect::<Vec<String>>();
+        let mut insert_fields: Vec<String> = Vec::new();
+        let mut insert_values: Vec<String> = Vec::new();
+        let mut row_args: Vec<FieldValue> = Vec::new();
+        construct_upsert_primary_key_value(&target_table.primary_key, &mut insert_fields, &mut insert_values, &mut row_args, &mut vec![]);
+        construct_upsert_fields_values(&target_table.columns, &mut insert_fields, &mut insert_values, &mut row_args, &mut vec![], &mut vec![], target_table.primary_key.iter().map(|it|it.get_col_name()).collect::<Vec<String>>());
+        match &fields {
+            None => fields = Some(insert_fields),
+            Some(first_fields) => if *first_fields != insert_fields {
+                return Err(QueryBuildError::new(BuildErrorType::OtherError, format!("every row of insert_many must set the same columns, expected ({}) but got ({})", first_fields.join(", "), insert_fields.join(", "))));
+            }
+        }
+        table_name = target_table.name;
+        values_list.push((format!("({})", insert_values.join(", ")), row_args));
+    }
+    let fields = match fields {
+        Some(fields) => fields,
+        None => return Ok(vec![]),
+    };
+
+    let prefix = format!("INSERT INTO {} ({}) VALUES ", table_name, fields.join(", "));
+    let mut suffix = "".to_string();
+    if update_on_duplicate {
+        let mut update_fields = fields.iter().filter(|field| !primary_key_names.contains(field)).collect::<Vec<&String>>();
+        if update_fields.is_empty() {
+            //only primary key columns, keep the existing rows
+            update_fields = fields.iter().take(1).collect();
+        }
+        let dialect = get_dialect();
+        let assignments = update_fields.iter().map(|field| format!("{} = {}", field, dialect.excluded_value(field))).collect::<Vec<String>>();
+        suffix = format!(" {}", dialect.upsert_clause(&primary_key_names, &assignments));
+    }
+
+    let mut statements: Vec<(String, Vec<FieldValue>)> = vec![];
+    let mut chunk_values: Vec<String> = vec![];
+    let mut chunk_args: Vec<FieldValue> = vec![];
+    let mut chunk_size = prefix.len() + suffix.len();
+    for (values, row_args) in values_list {
+        let row_size = values.len() + 2 + row_args.iter().map(estimated_size).sum::<usize>();
+        if !chunk_values.is_empty() && (chunk_size + row_size > max_packet_size || chunk_args.len() + row_args.len() > MAX_PLACEHOLDERS) {
+            statements.push((format!("{}{}{}", prefix, chunk_values.join(", "), suffix), chunk_args));
+            chunk_values = vec![];
+            chunk_args = vec![];
+            chunk_size = prefix.len() + suffix.len();
+        }
+        chunk_size += row_size;
+        chunk_values.push(values);
+        chunk_args.extend(row_args);
+    }
+    if !chunk_values.is_empty() {
+        statements.push((format!("{}{}{}", prefix, chunk_values.join(", "), suffix), chunk_args));
+    }
+    Ok(statements)
+}
+
+pub fn construct_upsert_primary_key_value(columns:&Vec<SqlColumn>, insert_fields: &mut Vec<String>, insert_values: &mut Vec<String>, insert_args: &mut Vec<FieldValue>, primary_key_as_conditions: &mut Vec<Condition>) {
     for primary_key_def in columns {
         match primary_key_def {
             SqlColumn::Varchar(column_def) => {
@@ -987,9 +1928,10 @@ pub fn construct_upsert_primary_key_value(columns:&Vec<SqlColumn>, insert_fields
                         }
                     }
                     if let Some(string_value) = value{
-                        insert_fields.push(col.name());
-                        insert_values.push(format!("'{}'", &string_value));
-                        primary_key_as_conditions.push(format!("{} = '{}'", col.name(), string_value));
+                        insert_fields.push(quote_name(&col.name()));
+                        insert_values.push("?".to_string());
+                        insert_args.push(FieldValue::String(string_value.clone()));
+                        primary_key_as_conditions.push(Condition::compare(quote_name(&col.name()), "=", FieldValue::String(string_value)));
                     }
                 }/*else{
                     return Err(QueryBuildError::new(BuildErrorType::MissingPrimaryKeyValue, "Primary key's value not found for upsert operation".to_string()));
@@ -1009,9 +1951,11 @@ pub fn construct_upsert_primary_key_value(columns:&Vec<SqlColumn>, insert_fields
                         }
                     }
                     if let Some(string_value) = value{
-                        insert_fields.push(col.name());
-                        insert_values.push(format!("'{}'", &string_value));
-                        primary_key_as_conditions.push(format!("{} = '{}'", col.name(), string_value));
+                        let value = if col.is_uuid() {FieldValue::Uuid(string_value)} else {FieldValue::String(string_value)};
+                        insert_fields.push(quote_name(&col.name()));
+                        insert_values.push("?".to_string());
+                        insert_args.push(value.clone());
+                        primary_key_as_conditions.push(Condition::compare(quote_name(&col.name()), "=", value));
                     }/*else{
                         return Err(QueryBuildError::new(BuildErrorType::MissingPrimaryKeyValue, "Empty primary key value for upsert operation".to_string()));
                     }*/
@@ -1022,9 +1966,10 @@ pub fn construct_upsert_primary_key_value(columns:&Vec<SqlColumn>, insert_fields
             SqlColumn::Int(column_def) => {
                 if let Some(col) = column_def {
                     if let Some(value) = col.value(){
-                        insert_fields.push(col.name());
-                        insert_values.push(value.to_string());
-                        primary_key_as_conditions.push(format!("{} = '{}'", col.name(), &value));
+                        insert_fields.push(quote_name(&col.name()));
+                        insert_values.push("?".to_string());
+                        insert_args.push(value.into());
+                        primary_key_as_conditions.push(Condition::compare(quote_name(&col.name()), "=", FieldValue::from(value)));
                     }/*else{
                         return Err(QueryBuildError::new(BuildErrorType::MissingPrimaryKeyValue, "Empty primary key value for upsert operation".to_string()));
                     }*/
@@ -1035,9 +1980,10 @@ pub fn construct_upsert_primary_key_value(columns:&Vec<SqlColumn>, insert_fields
             SqlColumn::Bigint(column_def) => {
                 if let Some(col) = column_def {
                     if let Some(value) = col.value(){
-                        insert_fields.push(col.name());
-                        insert_values.push(value.to_string());
-                        primary_key_as_conditions.push(format!("{} = '{}'", col.name(), &value));
+                        insert_fields.push(quote_name(&col.name()));
+                        insert_values.push("?".to_string());
+                        insert_args.push(value.into());
+                        primary_key_as_conditions.push(Condition::compare(quote_name(&col.name()), "=", FieldValue::from(value)));
                     }/*else{
                         return Err(QueryBuildError::new(BuildErrorType::MissingPrimaryKeyValue, "Empty primary key value for upsert operation".to_string()));
                     }*/
@@ -1048,9 +1994,10 @@ pub fn construct_upsert_primary_key_value(columns:&Vec<SqlColumn>, insert_fields
             SqlColumn::BigintUnsigned(column_def) => {
                 if let Some(col) = column_def {
                     if let Some(value) = col.value(){
-                        insert_fields.push(col.name());
-                        insert_values.push(value.to_string());
-                        primary_key_as_conditions.push(format!("{} = '{}'", col.name(), &value));
+                        insert_fields.push(quote_name(&col.name()));
+                        insert_values.push("?".to_string());
+                        insert_args.push(value.into());
+                        primary_key_as_conditions.push(Condition::compare(quote_name(&col.name()), "=", FieldValue::from(value)));
                     }/*else{
                         return Err(QueryBuildError::new(BuildErrorType::MissingPrimaryKeyValue, "Empty primary key value for upsert operation".to_string()));
                     }*/
@@ -1066,53 +2013,92 @@ pub fn construct_upsert_primary_key_value(columns:&Vec<SqlColumn>, insert_fields
 impl QueryBuilder {
 
     pub fn select_all_fields() -> QueryBuilder {
-        QueryBuilder { operation:Operation::Select, is_select_all: Some(true), distinct: None,count_all:None, target_table:None, select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![], */limit: None, order_by: vec![], group_by: vec![], update_values: vec![] }
+        QueryBuilder { operation:Operation::Select, is_select_all: Some(true), distinct: None,count_all:None, target_table:None, select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![], */limit: None, order_by: vec![], group_by: vec![], having: vec![], update_values: vec![], set_operation: None, insert_select: None, lock: None, hints: vec![], returning: vec![] }
+    }
+
+    ///select * from the queries combined by operator, order_by/limit/where apply to the combined result
+    pub fn init_with_set_operation(operator: SetOperator, queries: Vec<QueryBuilder>) -> QueryBuilder {
+        let mut query_builder = Self::select_all_fields();
+        query_builder.set_operation = Some(SetOperation { operator, queries, alias: "my_table".to_string() });
+        query_builder
+    }
+
+    ///alias of the combined result of union/intersect/except, "my_table" by default
+    pub fn alias(mut self, alias: &str) -> QueryBuilder {
+        if let Some(set_operation) = self.set_operation.as_mut() {
+            set_operation.alias = alias.to_string();
+        }
+        self
     }
 
     pub fn init_with_select_fields(fields: Vec<SelectField>) -> QueryBuilder {
         //let fields_strs = fields.iter().map(|field| field.name()).collect();
-        QueryBuilder { operation:Operation::Select, is_select_all:None, distinct: None, count_all:None,target_table:None, select_fields:fields, pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![],*/ limit: None, order_by: vec![], group_by: vec![], update_values: vec![] }
+        QueryBuilder { operation:Operation::Select, is_select_all:None, distinct: None, count_all:None,target_table:None, select_fields:fields, pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![],*/ limit: None, order_by: vec![], group_by: vec![], having: vec![], update_values: vec![], set_operation: None, insert_select: None, lock: None, hints: vec![], returning: vec![] }
     }
 
     pub fn init_with_select_all_fields<A>(table: & A) -> QueryBuilder where A : Table {
         //let fields_strs = fields.iter().map(|field| field.name()).collect();
-        QueryBuilder { operation:Operation::Select, is_select_all:Some(true), distinct: None, count_all:None, target_table:Some(TargetTable::new(table)), select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![],*/ limit: None, order_by: vec![], group_by: vec![], update_values: vec![] }
+        QueryBuilder { operation:Operation::Select, is_select_all:Some(true), distinct: None, count_all:None, target_table:Some(TargetTable::new(table)), select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![],*/ limit: None, order_by: vec![], group_by: vec![], having: vec![], update_values: vec![], set_operation: None, insert_select: None, lock: None, hints: vec![], returning: vec![] }
     }
 
     pub fn init_with_select_distinct_fields(fields: Vec<SelectField>) -> QueryBuilder {
         //let fields_strs = fields.iter().map(|field| field.name()).collect();
-        QueryBuilder { operation:Operation::Select, is_select_all:None, distinct: Some(true), count_all:None, target_table:None, select_fields:fields, pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![],*/ limit: None, order_by: vec![], group_by: vec![], update_values: vec![] }
+        QueryBuilder { operation:Operation::Select, is_select_all:None, distinct: Some(true), count_all:None, target_table:None, select_fields:fields, pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![],*/ limit: None, order_by: vec![], group_by: vec![], having: vec![], update_values: vec![], set_operation: None, insert_select: None, lock: None, hints: vec![], returning: vec![] }
     }
 
     pub fn insert_into_table_with_value<A>(table:& A) -> QueryBuilder where A : Table{
         //table.insert_query_builder()
-        QueryBuilder { operation:Operation::Insert, is_select_all:None, distinct: None, count_all:None, target_table:Some(TargetTable::new(table)), select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![],*/ limit: None, order_by: vec![], group_by: vec![], update_values: vec![] }
+        QueryBuilder { operation:Operation::Insert, is_select_all:None, distinct: None, count_all:None, target_table:Some(TargetTable::new(table)), select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![],*/ limit: None, order_by: vec![], group_by: vec![], having: vec![], update_values: vec![], set_operation: None, insert_select: None, lock: None, hints: vec![], returning: vec![] }
+    }
+
+    ///INSERT INTO table (columns) SELECT ..., set them with columns() and select()
+    pub fn insert_into<A>(table:& A) -> QueryBuilder where A : Table{
+        QueryBuilder { operation:Operation::InsertSelect, is_select_all:None, distinct: None, count_all:None, target_table:Some(TargetTable::new(table)), select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![],*/ limit: None, order_by: vec![], group_by: vec![], having: vec![], update_values: vec![], set_operation: None, insert_select: Some(InsertSelect::default()), lock: None, hints: vec![], returning: vec![] }
+    }
+
+    ///the target columns of insert_into, in the order of the selected fields
+    pub fn columns<T: Into<SelectField>>(mut self, fields: Vec<T>) -> QueryBuilder {
+        let fields = fields.into_iter().map(|field| field.into().unset_as()).collect::<Vec<SelectField>>();
+        self.insert_select.get_or_insert_with(InsertSelect::default).columns.extend(fields);
+        self
+    }
+
+    ///the query whose rows insert_into inserts
+    pub fn select(mut self, query: QueryBuilder) -> QueryBuilder {
+        self.insert_select.get_or_insert_with(InsertSelect::default).query = Some(Box::new(query));
+        self
+    }
+
+    ///updates the non primary key columns of insert_into when a row with the same key exists
+    pub fn update_on_duplicate(mut self) -> QueryBuilder {
+        self.insert_select.get_or_insert_with(InsertSelect::default).update_on_duplicate = true;
+        self
     }
 
     pub fn update<A>(table:& A) -> QueryBuilder where A : Table{
         //table.update_query_builder()
-        QueryBuilder { operation:Operation::Update_By_Condition,is_select_all:None, distinct: None, count_all:None, target_table:Some(TargetTable::new(table)), select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![], */limit: None, order_by: vec![], group_by: vec![], update_values: vec![] }
+        QueryBuilder { operation:Operation::Update_By_Condition,is_select_all:None, distinct: None, count_all:None, target_table:Some(TargetTable::new(table)), select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![], */limit: None, order_by: vec![], group_by: vec![], having: vec![], update_values: vec![], set_operation: None, insert_select: None, lock: None, hints: vec![], returning: vec![] }
     }
 
     pub fn update_table_with_value<A>(table:& A) -> QueryBuilder where A : Table{
         //table.update_query_builder()
-        QueryBuilder { operation:Operation::Update_By_PrimaryKey,is_select_all:None, distinct: None, count_all:None, target_table:Some(TargetTable::new(table)), select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![], */limit: None, order_by: vec![], group_by: vec![], update_values: vec![] }
+        QueryBuilder { operation:Operation::Update_By_PrimaryKey,is_select_all:None, distinct: None, count_all:None, target_table:Some(TargetTable::new(table)), select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![], */limit: None, order_by: vec![], group_by: vec![], having: vec![], update_values: vec![], set_operation: None, insert_select: None, lock: None, hints: vec![], returning: vec![] }
     }
 
     pub fn upsert_table_with_value<A>(table:& A) -> QueryBuilder where A : Table{
-        QueryBuilder { operation:Operation::Insert_Or_Update,is_select_all:None, distinct: None, count_all:None, target_table:Some(TargetTable::new(table)), select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![],*/ limit: None, order_by: vec![], group_by: vec![], update_values: vec![] }
+        QueryBuilder { operation:Operation::Insert_Or_Update,is_select_all:None, distinct: None, count_all:None, target_table:Some(TargetTable::new(table)), select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![],*/ limit: None, order_by: vec![], group_by: vec![], having: vec![], update_values: vec![], set_operation: None, insert_select: None, lock: None, hints: vec![], returning: vec![] }
     }
 
     pub fn delete_one_from<A>(table:& A) -> QueryBuilder where A : Table{
-        QueryBuilder { operation:Operation::Delete,is_select_all:None, distinct: None, count_all:None, target_table:Some(TargetTable::new(table)), select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![], */limit: Some(Limit::new(0, 1)), order_by: vec![], group_by: vec![], update_values: vec![] }
+        QueryBuilder { operation:Operation::Delete,is_select_all:None, distinct: None, count_all:None, target_table:Some(TargetTable::new(table)), select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![],/* upsert_values: vec![], */limit: Some(Limit::new(0, 1)), order_by: vec![], group_by: vec![], having: vec![], update_values: vec![], set_operation: None, insert_select: None, lock: None, hints: vec![], returning: vec![] }
     }
 
     pub fn delete_one_where<A>(table:& A,condition: Condition) -> QueryBuilder where A : Table{
-        QueryBuilder { operation:Operation::Delete,is_select_all:None, distinct: None, count_all:None, target_table:Some(TargetTable::new(table)), select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![condition],/* upsert_values: vec![], */limit: Some(Limit::new(0, 1)), order_by: vec![], group_by: vec![], update_values: vec![] }
+        QueryBuilder { operation:Operation::Delete,is_select_all:None, distinct: None, count_all:None, target_table:Some(TargetTable::new(table)), select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![condition],/* upsert_values: vec![], */limit: Some(Limit::new(0, 1)), order_by: vec![], group_by: vec![], having: vec![], update_values: vec![], set_operation: None, insert_select: None, lock: None, hints: vec![], returning: vec![] }
     }
 
     pub fn delete_all_where<A>(table:& A,condition: Condition) -> QueryBuilder where A : Table{
-        QueryBuilder { operation:Operation::Delete,is_select_all:None, distinct: None, count_all:None, target_table:Some(TargetTable::new(table)), select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![condition],/* upsert_values: vec![], */limit: None, order_by: vec![], group_by: vec![], update_values: vec![] }
+        QueryBuilder { operation:Operation::Delete,is_select_all:None, distinct: None, count_all:None, target_table:Some(TargetTable::new(table)), select_fields:vec![], pending_join: None, joins: vec![], conditions: vec![condition],/* upsert_values: vec![], */limit: None, order_by: vec![], group_by: vec![], having: vec![], update_values: vec![], set_operation: None, insert_select: None, lock: None, hints: vec![], returning: vec![] }
     }
 
     pub fn from<A>(mut self, table:& A) -> QueryBuilder where A : Table{
@@ -1144,6 +2130,17 @@ impl QueryBuilder {
         self
     }
 
+    pub fn right_join<A>(mut self, table:& A) -> QueryBuilder where A : Table{
+        self.pending_join = Some(TableJoin::new(TargetTable::new(table),RIGHT,None));
+        self
+    }
+
+    ///cross join takes no on() or using()
+    pub fn cross_join<A>(mut self, table:& A) -> QueryBuilder where A : Table{
+        self.joins.push(TableJoin::new(TargetTable::new(table),CROSS,None));
+        self
+    }
+
     pub fn add_join(mut self, join:TableJoin) -> QueryBuilder {
         self.joins.push(join);
         self
@@ -1157,6 +2154,21 @@ impl QueryBuilder {
         self
     }
 
+    ///joins the pending join on the columns with the same name in both tables, e.g. `.left_join(&t).using(vec![t.user_id()])`
+    pub fn using<T: Into<SelectField>>(mut self, fields: Vec<T>) -> QueryBuilder {
+        let columns = fields.into_iter()
+            .map(|field| match field.into() {
+                SelectField::Field(field) => field.name,
+                field => field.to_string(),
+            })
+            .collect::<Vec<String>>();
+        if self.pending_join.is_some() {
+            self.joins.push(self.pending_join.unwrap().apply_using(columns));
+            self.pending_join = None;
+        }
+        self
+    }
+
     pub fn order_by<T: Into<SelectField>>(mut self, fields: Vec<T>) -> QueryBuilder{
         let fields: Vec<SelectField> = fields.into_iter()
             .map(|field| field.into())
@@ -1175,18 +2187,32 @@ impl QueryBuilder {
         self
     }
 
+    ///filters the groups of group_by, e.g. `.having(count_all().gt(1))`, multiple calls are joined with AND
+    pub fn having(mut self, condition: Condition) -> QueryBuilder {
+        self.having.push(condition);
+        self
+    }
+
+    ///the target table without its quotes, as reported in traces and metrics, empty for a set operation
+    pub(crate) fn table_name(&self) -> String {
+        self.target_table.as_ref().map_or(String::new(), |table| table.name.replace(['`', '"'], ""))
+    }
+
     pub fn as_table(mut self, table: &str) -> InnerTable {
         let build_result = self.build();
-        if let Ok(query_string) = build_result {
-            println!("query string # {}", query_string);
+        if let Ok((query_string, args)) = build_result {
             InnerTable {
-                table_name: format!("({}) as {}", query_string, table),
-                map_fields: self.select_fields.iter().map(|field| (field.clone().to_string(), Varchar::with_name(field.clone().to_string()))).collect::<HashMap<String, Varchar>>(),
+                table_name: format!("({}) as {}", query_string, quote_name(table)),
+                alias: table.to_string(),
+                map_fields: self.select_fields.iter().map(|field| (field.output_name(), Varchar::with_qualified_name(table.to_string(), field.output_name()))).collect::<HashMap<String, Varchar>>(),
+                args,
             }
         }else{
             InnerTable{
                 table_name: "".to_string(),
+                alias: table.to_string(),
                 map_fields: Default::default(),
+                args: vec![],
             }
             
         }
@@ -1212,6 +2238,19 @@ impl QueryBuilder {
         self
     }
 
+    ///where_ when the condition is present, None leaves the query unchanged
+    pub fn where_opt(self, condition: Option<Condition>) -> QueryBuilder {
+        match condition {
+            Some(condition) => self.where_(condition),
+            None => self,
+        }
+    }
+
+    ///applies build to the query only when flag is true, e.g. `.when(params.lock, |q| q.for_update())`
+    pub fn when<F>(self, flag: bool, build: F) -> QueryBuilder where F: FnOnce(QueryBuilder) -> QueryBuilder {
+        if flag {build(self)} else {self}
+    }
+
     /// 设置要更新的字段和值
     pub fn set<T, V>(mut self, field: T, value: V) -> QueryBuilder
     where
@@ -1222,6 +2261,87 @@ impl QueryBuilder {
         self
     }
 
+    ///sets field to another column or expression instead of a value, e.g. a column of a joined table
+    pub fn set_field<T, F>(mut self, field: T, value: F) -> QueryBuilder
+    where
+        T: Into<SelectField>,
+        F: Into<SelectField>,
+    {
+        let value = match value.into().unset_as() {
+            SelectField::Field(mut value) => {
+                value.is_encrypted = false;
+                value
+            }
+            value => Field::new("", &value.to_string(), None, None, false),
+        };
+        self.update_values.push((field.into(), FieldValue::Field(value)));
+        self
+    }
+
+    ///adds an index hint to the table given last to from or a join
+    fn add_index_hint(mut self, hint: IndexHint) -> QueryBuilder {
+        if let Some(pending_join) = self.pending_join.as_mut() {
+            pending_join.target_table.index_hints.push(hint);
+        }else if let Some(join) = self.joins.last_mut() {
+            join.target_table.index_hints.push(hint);
+        }else if let Some(target_table) = self.target_table.as_mut() {
+            target_table.index_hints.push(hint);
+        }
+        self
+    }
+
+    ///USE INDEX for the table given last to from or a join, e.g. `.from(&product).use_index(vec![ProductTable::INDEX_IDX_SUPPLIER_ID])`
+    pub fn use_index<T: Into<String>>(self, indexes: Vec<T>) -> QueryBuilder {
+        self.add_index_hint(IndexHint::Use(indexes.into_iter().map(|index| index.into()).collect()))
+    }
+
+    ///FORCE INDEX for the table given last to from or a join
+    pub fn force_index<T: Into<String>>(self, indexes: Vec<T>) -> QueryBuilder {
+        self.add_index_hint(IndexHint::Force(indexes.into_iter().map(|index| index.into()).collect()))
+    }
+
+    ///IGNORE INDEX for the table given last to from or a join
+    pub fn ignore_index<T: Into<String>>(self, indexes: Vec<T>) -> QueryBuilder {
+        self.add_index_hint(IndexHint::Ignore(indexes.into_iter().map(|index| index.into()).collect()))
+    }
+
+    ///adds an optimizer hint to the select, e.g. `.hint(OptimizerHint::MaxExecutionTime(1000))`
+    pub fn hint(mut self, hint: OptimizerHint) -> QueryBuilder {
+        self.hints.push(hint);
+        self
+    }
+
+    ///locks the selected rows until the transaction ends, only useful inside transaction()
+    pub fn for_update(mut self) -> QueryBuilder {
+        self.lock.get_or_insert(RowLock { mode: None, wait: LockWait::Wait }).mode = Some(LockMode::Update);
+        self
+    }
+
+    ///locks the selected rows against writes of other transactions until the transaction ends
+    pub fn for_share(mut self) -> QueryBuilder {
+        self.lock.get_or_insert(RowLock { mode: None, wait: LockWait::Wait }).mode = Some(LockMode::Share);
+        self
+    }
+
+    ///fails right away instead of waiting when a row is locked by another transaction
+    pub fn nowait(mut self) -> QueryBuilder {
+        self.lock.get_or_insert(RowLock { mode: None, wait: LockWait::Wait }).wait = LockWait::NoWait;
+        self
+    }
+
+    ///leaves out rows locked by other transactions, e.g. to take jobs from a queue table
+    pub fn skip_locked(mut self) -> QueryBuilder {
+        self.lock.get_or_insert(RowLock { mode: None, wait: LockWait::Wait }).wait = LockWait::SkipLocked;
+        self
+    }
+
+    ///columns of the inserted, updated or deleted rows to send back, read them with fetch_postgres or fetch_sqlite.
+    ///needs a dialect supporting RETURNING, i.e. PostgreSQL or SQLite 3.35 and later
+    pub fn returning<T: Into<SelectField>>(mut self, fields: Vec<T>) -> QueryBuilder {
+        self.returning = fields.into_iter().map(|field| field.into()).collect();
+        self
+    }
+
     pub fn limit(mut self, limit: i32) -> QueryBuilder {
         self.limit = Some(Limit::new(0, limit));
         self
@@ -1244,12 +2364,20 @@ impl QueryBuilder {
 
     ///execute insert/update/delete and return the affected rows number
     pub async fn execute(&self) -> Result<MySqlQueryResult,Error> {
-        let pool = POOL.get().unwrap();
+        on_default_pool(self.execute_with(default_pool()?)).await
+    }
+
+    ///execute insert/update/delete inside a transaction
+    pub async fn execute_in(&self, tx: &TransactionHandle) -> Result<MySqlQueryResult,Error> {
+        self.execute_with(&mut **tx.lock().await).await
+    }
+
+    ///execute on the given executor: a pool (`&pool`), a connection (`&mut conn`) or a transaction (`&mut *tx`)
+    pub async fn execute_with<'e, E>(&self, executor: E) -> Result<MySqlQueryResult,Error> where E: Executor<'e, Database = MySql> {
         let build_result = self.build();
-        if let Ok(query_string) = build_result {
-            println!("query string # {}", query_string);
-            let result = sqlx::query(&query_string).execute(pool).await? as MySqlQueryResult; // Pass the reference to sqlx::query()
-            Ok(result)
+        if let Ok((query_string, args)) = build_result {
+            let query = sqlx::query_with(&query_string, to_mysql_arguments(&args)?).execute(executor);
+            intercept(&query_string, &args, &self.operation, &self.table_name(), MySqlQueryResult::rows_affected, query).await
         }else if let Err(e) = build_result {
             Err(Error::Configuration(e.message.into()))
         }else {
@@ -1257,19 +2385,62 @@ impl QueryBuilder {
         }
     }
 
-    pub async fn fetch<T: Serialize + for<'de> serde::Deserialize<'de>>(&self) -> Result<Vec<T>, Error> {
+    ///runs a delete batch_size rows at a time until no matching row is left, so a large purge does not hold its locks for long.
+    ///returns the number of deleted rows
+    pub async fn delete_in_batches(&self, batch_size: i32) -> Result<u64, Error> {
+        on_default_pool(self.delete_in_batches_with(default_pool()?, batch_size)).await
+    }
 
-        let pool = POOL.get().unwrap();
-        let build_result = self.build();
-        if let Ok(query_string) = build_result {
-            println!("query string # {}", query_string);
+    ///delete_in_batches on the given pool, every batch is a statement of its own
+    pub async fn delete_in_batches_with(&self, pool: &MySqlPool, batch_size: i32) -> Result<u64, Error> {
+        if let Operation::Delete = self.operation {} else {
+            return Err(Error::Configuration("delete_in_batches only supports delete operations".into()));
+        }
+        if batch_size <= 0 {
+            return Err(Error::Configuration("batch_size of delete_in_batches must be greater than 0".into()));
+        }
+        let mut batch = self.clone();
+        batch.limit = Some(Limit::new(0, batch_size));
+        let mut rows_deleted: u64 = 0;
+        loop {
+            //a short batch does not mean nothing is left, rows may be inserted meanwhile, so stop only on an empty one
+            let rows_affected = batch.execute_with(pool).await?.rows_affected();
+            if rows_affected == 0 {
+                break;
+            }
+            rows_deleted += rows_affected;
+        }
+        Ok(rows_deleted)
+    }
+
+    ///rows of types registered with register_row_mappable are decoded by RowMappable::from_row,
+    ///the others are converted to json and deserialized into T
+    pub async fn fetch<T: Serialize + for<'de> serde::Deserialize<'de> + 'static>(&self) -> Result<Vec<T>, Error> {
+        on_default_pool(self.fetch_with(default_pool()?)).await
+    }
+
+    ///fetch inside a transaction
+    pub async fn fetch_in<T: Serialize + for<'de> serde::Deserialize<'de> + 'static>(&self, tx: &TransactionHandle) -> Result<Vec<T>, Error> {
+        self.fetch_with(&mut **tx.lock().await).await
+    }
 
-            let jsons = sqlx::query(&query_string)
+    ///fetch on the given executor: a pool (`&pool`), a connection (`&mut conn`) or a transaction (`&mut *tx`)
+    pub async fn fetch_with<'e, E, T: Serialize + for<'de> serde::Deserialize<'de> + 'static>(&self, executor: E) -> Result<Vec<T>, Error> where E: Executor<'e, Database = MySql> {
+        let build_result = self.build();
+        if let Ok((query_string, args)) = build_result {
+            if let Some(row_mapper) = get_row_mapper::<T>() {
+                let query = sqlx::query_with(&query_string, to_mysql_arguments(&args)?)
+                    .try_map(|row: MySqlRow| row_mapper(&row))
+                    .fetch_all(executor);
+                let rows = intercept(&query_string, &args, &self.operation, &self.table_name(), |rows| rows.len() as u64, query).await?;
+                return rows.into_iter().map(downcast_row::<T>).collect();
+            }
+            let query = sqlx::query_with(&query_string, to_mysql_arguments(&args)?)
                 .try_map(|row:MySqlRow| {
                     self.convert_to_json_value(row)
                 })
-                .fetch_all(pool)
-                .await?;
+                .fetch_all(executor);
+            let jsons = intercept(&query_string, &args, &self.operation, &self.table_name(), |rows| rows.len() as u64, query).await?;
 
             let mut result = Vec::new();
             for json in jsons {
@@ -1278,7 +2449,7 @@ impl QueryBuilder {
                     Ok(item_parsed) => {
                         result.push(item_parsed);
                     }
-                    Err(err) => {println!("error={:?}", err);}
+                    Err(err) => {log::warn!("error={:?}", err);}
                 }
                 // if let Ok(item_parsed) = item_parsed_result {
                 //     result.push(item_parsed);
@@ -1295,10 +2466,106 @@ impl QueryBuilder {
         }
     }
 
+    ///like fetch, but yields the rows one at a time as they arrive instead of collecting them, e.g. for large exports
+    pub fn fetch_stream<T: Serialize + for<'de> serde::Deserialize<'de> + 'static>(&self) -> impl Stream<Item = Result<T, Error>> + 'static {
+        let query_builder = self.clone();
+        try_stream! {
+            let mut items = Box::pin(query_builder.fetch_stream_with::<_, T>(default_pool()?));
+            //the scope is entered on every poll, the last one explains the statement when it was slow
+            while let Some(item) = on_default_pool(items.try_next()).await? {
+                yield item;
+            }
+        }
+    }
+
+    ///fetch_stream on the given executor, the stream borrows it until it is dropped
+    pub fn fetch_stream_with<'e, E, T: Serialize + for<'de> serde::Deserialize<'de>>(&self, executor: E) -> impl Stream<Item = Result<T, Error>> + 'e where E: Executor<'e, Database = MySql> + 'e, T: 'e {
+        let query_builder = self.clone();
+        try_stream! {
+            let (query_string, args) = query_builder.build().map_err(|e| Error::Configuration(e.message.into()))?;
+            let execution = QueryExecution::start(&query_string, &args, &query_builder.operation, &query_builder.table_name());
+            let mut rows = sqlx::query_with(&query_string, to_mysql_arguments(&args)?).fetch(executor);
+            loop {
+                let row = match execution.await_row(rows.try_next()).await {
+                    Ok(Some(row)) => row,
+                    Ok(None) => break,
+                    Err(error) => {
+                        execution.finish(0, Some(&error));
+                        Err(error)?
+                    }
+                };
+                execution.fetched_row();
+                let json = query_builder.convert_to_json_value(row)?;
+                let item = serde_json::from_value::<T>(json).map_err(|err| Error::Configuration(err.into()))?;
+                yield item;
+            }
+            let duration = execution.finish(execution.fetched_rows(), None);
+            execution.check_slow_query(duration);
+        }
+    }
+
+    ///keyset pagination: the page_size rows after cursor in order_by order, without the offset and count(*) of fetch_paging.
+    ///pass None for the first page and next_cursor/prev_cursor of the result for the others.
+    ///order_by must end with a unique column such as the primary key, and its columns must be selected
+    pub async fn fetch_after<T: Serialize + for<'de> serde::Deserialize<'de>>(&self, cursor: Option<&str>, page_size: i32) -> Result<CursorPagingData<T>, Error> {
+        on_default_pool(self.fetch_after_with(default_pool()?, cursor, page_size)).await
+    }
+
+    ///fetch_after on the given executor
+    pub async fn fetch_after_with<'e, E, T: Serialize + for<'de> serde::Deserialize<'de>>(&self, executor: E, cursor: Option<&str>, page_size: i32) -> Result<CursorPagingData<T>, Error> where E: Executor<'e, Database = MySql> {
+        if self.order_by.is_empty() {
+            return Err(Error::Configuration("fetch_after needs order_by, ending with a unique column such as the primary key".into()));
+        }
+        if page_size < 1 {
+            return Err(Error::Configuration("page_size of fetch_after must be greater than 0".into()));
+        }
+        let keys = seek_keys(&self.order_by, &self.select_fields);
+        let cursor = match cursor {
+            Some(cursor) => Some(PageCursor::decode(cursor)?),
+            None => None,
+        };
+        let before = cursor.as_ref().map_or(false, |cursor| cursor.before);
+
+        let mut query_builder = self.clone();
+        if let Some(cursor) = &cursor {
+            if cursor.values.len() != keys.len() {
+                return Err(Error::Configuration("the cursor does not match the order_by of the query".into()));
+            }
+            let values = cursor.values.iter().map(CursorValue::to_field_value).collect::<Result<Vec<FieldValue>, Error>>()?;
+            query_builder.conditions.push(seek_condition(&keys, values, before));
+        }
+        if before {
+            //walk backwards from the cursor, the rows are reversed again below
+            query_builder.order_by = keys.iter().map(|key| SelectField::Untyped(format!("{} {}", key.expression, if key.descending { "asc" } else { "desc" }))).collect();
+        }
+        //one more row tells whether there is another page
+        query_builder.limit = Some(Limit::new(0, page_size + 1));
+
+        let (query_string, args) = query_builder.build().map_err(|e| Error::Configuration(e.message.into()))?;
+        let query = sqlx::query_with(&query_string, to_mysql_arguments(&args)?)
+            .try_map(|row:MySqlRow| {
+                let mut values = vec![];
+                for key in keys.iter() {
+                    match row.columns().iter().position(|column| column.name() == key.column) {
+                        Some(index) => values.push(CursorValue::of(&row, index)?),
+                        None => return Err(Error::Configuration(format!("order_by column {} must be selected for fetch_after", key.column).into())),
+                    }
+                }
+                Ok((self.convert_to_json_value(row)?, values))
+            })
+            .fetch_all(executor);
+        let rows = intercept(&query_string, &args, &self.operation, &self.table_name(), |rows| rows.len() as u64, query).await?;
+        cursor_page(rows, page_size, before, cursor.is_some())
+    }
+
     ///paging query with parallel count and data fetch
     pub async fn fetch_paging_parallel<T: Serialize + for<'de> serde::Deserialize<'de>>(&self) -> Result<PagingData<T>, Error> {
+        on_default_pool(self.fetch_paging_parallel_with(default_pool()?)).await
+    }
+
+    ///paging query with parallel count and data fetch on the given pool
+    pub async fn fetch_paging_parallel_with<T: Serialize + for<'de> serde::Deserialize<'de>>(&self, pool: &MySqlPool) -> Result<PagingData<T>, Error> {
 
-        let pool = POOL.get().unwrap();
         let mut count_query_builder = self.clone();
         count_query_builder.select_fields = vec![SelectField::Untyped("count(*)".to_string())];
         count_query_builder.limit = None;
@@ -1316,30 +2583,30 @@ impl QueryBuilder {
         let count_query_build_result = count_query_builder.build();
         let data_query_build_result = data_query_builder.build();
 
-        let count_query_string = match count_query_build_result {
+        let (count_query_string, count_args) = match count_query_build_result {
             Ok(q) => q,
             Err(e) => return Err(Error::Configuration(e.message.into())),
         };
 
-        let data_query_string = match data_query_build_result {
+        let (data_query_string, data_args) = match data_query_build_result {
             Ok(q) => q,
             Err(e) => return Err(Error::Configuration(e.message.into())),
         };
 
-        println!("count query string # {}", count_query_string);
-        println!("query string # {}", data_query_string);
-
-        let count_future = sqlx::query(&count_query_string)
+        let count_query = sqlx::query_with(&count_query_string, to_mysql_arguments(&count_args)?)
             .try_map(|row:MySqlRow| {
                 self.convert_to_number(row)
             })
             .fetch_one(pool);
+        let table_name = self.table_name();
+        let count_future = intercept(&count_query_string, &count_args, &self.operation, &table_name, |_| 1, count_query);
 
-        let data_future = sqlx::query(&data_query_string)
+        let data_query = sqlx::query_with(&data_query_string, to_mysql_arguments(&data_args)?)
             .try_map(|row:MySqlRow| {
                 self.convert_to_json_value(row)
             })
             .fetch_all(pool);
+        let data_future = intercept(&data_query_string, &data_args, &self.operation, &table_name, |rows| rows.len() as u64, data_query);
 
         let (count_result, data_rows) = tokio::try_join!(count_future, data_future)?;
 
@@ -1353,7 +2620,7 @@ impl QueryBuilder {
                     result.push(item_parsed);
                 }
                 Err(err) => {
-                    println!("error={:?}", err);
+                    log::warn!("error={:?}", err);
                     return Err(Error::Configuration(err.into()))
                 }
             }
@@ -1366,8 +2633,17 @@ impl QueryBuilder {
 
     ///paging query
     pub async fn fetch_paging<T: Serialize + for<'de> serde::Deserialize<'de>>(&self) -> Result<PagingData<T>, Error> {
+        let mut conn = default_pool()?.acquire().await?;
+        on_default_pool(self.fetch_paging_with(&mut conn)).await
+    }
+
+    ///paging query inside a transaction
+    pub async fn fetch_paging_in<T: Serialize + for<'de> serde::Deserialize<'de>>(&self, tx: &TransactionHandle) -> Result<PagingData<T>, Error> {
+        self.fetch_paging_with(&mut **tx.lock().await).await
+    }
 
-        let pool = POOL.get().unwrap();
+    ///paging query on the given connection, count and data queries run one after another on it
+    pub async fn fetch_paging_with<T: Serialize + for<'de> serde::Deserialize<'de>>(&self, conn: &mut MySqlConnection) -> Result<PagingData<T>, Error> {
         let mut count_query_builder = self.clone();
         count_query_builder.select_fields = vec![SelectField::Untyped("count(*)".to_string())];
         count_query_builder.limit = None;
@@ -1376,14 +2652,13 @@ impl QueryBuilder {
 
         let mut count = 0;
 
-        if let Ok(query_string) = count_query_build_result {
-            println!("count query string # {}", query_string);
-            count = sqlx::query(&query_string)
+        if let Ok((query_string, args)) = count_query_build_result {
+            let query = sqlx::query_with(&query_string, to_mysql_arguments(&args)?)
                 .try_map(|row:MySqlRow| {
                     self.convert_to_number(row)
                 })
-                .fetch_one(pool)
-                .await?;
+                .fetch_one(&mut *conn);
+            count = intercept(&query_string, &args, &self.operation, &self.table_name(), |_| 1, query).await?;
 
 
         }else if let Err(e) = count_query_build_result {
@@ -1402,15 +2677,13 @@ impl QueryBuilder {
         }
 
         let build_result = query_builder.build();
-        if let Ok(query_string) = build_result {
-            println!("query string # {}", query_string);
-
-            let jsons = sqlx::query(&query_string)
+        if let Ok((query_string, args)) = build_result {
+            let query = sqlx::query_with(&query_string, to_mysql_arguments(&args)?)
                 .try_map(|row:MySqlRow| {
                     self.convert_to_json_value(row)
                 })
-                .fetch_all(pool)
-                .await?;
+                .fetch_all(&mut *conn);
+            let jsons = intercept(&query_string, &args, &self.operation, &self.table_name(), |rows| rows.len() as u64, query).await?;
 
             let mut result = Vec::new();
             for json in jsons {
@@ -1420,7 +2693,7 @@ impl QueryBuilder {
                         result.push(item_parsed);
                     }
                     Err(err) => {
-                        println!("error={:?}", err);
+                        log::warn!("error={:?}", err);
                         return Err(Error::Configuration(err.into()))
                     }
                 }
@@ -1435,21 +2708,38 @@ impl QueryBuilder {
         }
     }
 
-    pub async fn fetch_one<T: Serialize + for<'de> serde::Deserialize<'de>>(&mut self) -> Result<Option<T>,Error> {
+    ///the row of a type registered with register_row_mappable is decoded by RowMappable::from_row,
+    ///otherwise it is converted to json and deserialized into T
+    pub async fn fetch_one<T: Serialize + for<'de> serde::Deserialize<'de> + 'static>(&mut self) -> Result<Option<T>,Error> {
+        on_default_pool(self.fetch_one_with(default_pool()?)).await
+    }
+
+    ///fetch_one inside a transaction
+    pub async fn fetch_one_in<T: Serialize + for<'de> serde::Deserialize<'de> + 'static>(&mut self, tx: &TransactionHandle) -> Result<Option<T>,Error> {
+        self.fetch_one_with(&mut **tx.lock().await).await
+    }
+
+    ///fetch_one on the given executor: a pool (`&pool`), a connection (`&mut conn`) or a transaction (`&mut *tx`)
+    pub async fn fetch_one_with<'e, E, T: Serialize + for<'de> serde::Deserialize<'de> + 'static>(&mut self, executor: E) -> Result<Option<T>,Error> where E: Executor<'e, Database = MySql> {
 
         self.limit = Some(Limit::new(0, 1));
 
-        let pool = POOL.get().unwrap();
         let build_result = self.build();
-        if let Ok(query_string) = build_result {
-            println!("query string # {}", query_string);
-
-            let query_result = sqlx::query(&query_string)
+        if let Ok((query_string, args)) = build_result {
+            if let Some(row_mapper) = get_row_mapper::<T>() {
+                let query = sqlx::query_with(&query_string, to_mysql_arguments(&args)?)
+                    .try_map(|row: MySqlRow| row_mapper(&row))
+                    .fetch_optional(executor);
+                let row = intercept(&query_string, &args, &self.operation, &self.table_name(), |row| row.is_some() as u64, query).await?;
+                return row.map(downcast_row::<T>).transpose();
+            }
+            let query = sqlx::query_with(&query_string, to_mysql_arguments(&args)?)
                 .try_map(|row:MySqlRow| {
                     self.convert_to_json_value(row)
                 })
-                .fetch_one(pool)
-                .await;
+                .fetch_optional(executor);
+            let query_result = intercept(&query_string, &args, &self.operation, &self.table_name(), |row| row.is_some() as u64, query).await
+                .and_then(|row| row.ok_or(Error::RowNotFound));
 
             match query_result {
                 Ok(result)=>{
@@ -1518,18 +2808,82 @@ impl QueryBuilder {
             Err(Error::Encode("未知错误".into()))
         }
     }
+
+    ///fetch decoding every row with RowMappable::from_row instead of going through json
+    pub async fn fetch_mapped<T: RowMappable + Send + Unpin>(&self) -> Result<Vec<T>, Error> {
+        on_default_pool(self.fetch_mapped_with(default_pool()?)).await
+    }
+
+    ///fetch_mapped inside a transaction
+    pub async fn fetch_mapped_in<T: RowMappable + Send + Unpin>(&self, tx: &TransactionHandle) -> Result<Vec<T>, Error> {
+        self.fetch_mapped_with(&mut **tx.lock().await).await
+    }
+
+    ///fetch_mapped on the given executor: a pool (`&pool`), a connection (`&mut conn`) or a transaction (`&mut *tx`)
+    pub async fn fetch_mapped_with<'e, E, T: RowMappable + Send + Unpin>(&self, executor: E) -> Result<Vec<T>, Error> where E: Executor<'e, Database = MySql> {
+        let (query_string, args) = self.build().map_err(|e| Error::Configuration(e.message.into()))?;
+        let query = sqlx::query_with(&query_string, to_mysql_arguments(&args)?)
+            .try_map(|row: MySqlRow| T::from_row(&row))
+            .fetch_all(executor);
+        intercept(&query_string, &args, &self.operation, &self.table_name(), |rows| rows.len() as u64, query).await
+    }
+
+    ///fetch_one decoding the row with RowMappable::from_row instead of going through json
+    pub async fn fetch_one_mapped<T: RowMappable + Send + Unpin>(&mut self) -> Result<Option<T>, Error> {
+        on_default_pool(self.fetch_one_mapped_with(default_pool()?)).await
+    }
+
+    ///fetch_one_mapped inside a transaction
+    pub async fn fetch_one_mapped_in<T: RowMappable + Send + Unpin>(&mut self, tx: &TransactionHandle) -> Result<Option<T>, Error> {
+        self.fetch_one_mapped_with(&mut **tx.lock().await).await
+    }
+
+    ///fetch_one_mapped on the given executor: a pool (`&pool`), a connection (`&mut conn`) or a transaction (`&mut *tx`)
+    pub async fn fetch_one_mapped_with<'e, E, T: RowMappable + Send + Unpin>(&mut self, executor: E) -> Result<Option<T>, Error> where E: Executor<'e, Database = MySql> {
+        self.limit = Some(Limit::new(0, 1));
+
+        let (query_string, args) = self.build().map_err(|e| Error::Configuration(e.message.into()))?;
+        let query = sqlx::query_with(&query_string, to_mysql_arguments(&args)?)
+            .try_map(|row: MySqlRow| T::from_row(&row))
+            .fetch_optional(executor);
+        intercept(&query_string, &args, &self.operation, &self.table_name(), |row| row.is_some() as u64, query).await
+    }
+
+    ///the plan of this statement from `EXPLAIN FORMAT=JSON`, e.g. to check in tests that a query uses an index
+    pub async fn explain(&self) -> Result<QueryPlan, Error> {
+        self.explain_with(default_pool()?).await
+    }
+
+    ///explain inside a transaction
+    pub async fn explain_in(&self, tx: &TransactionHandle) -> Result<QueryPlan, Error> {
+        self.explain_with(&mut **tx.lock().await).await
+    }
+
+    ///explain on the given executor: a pool (`&pool`), a connection (`&mut conn`) or a transaction (`&mut *tx`)
+    pub async fn explain_with<'e, E>(&self, executor: E) -> Result<QueryPlan, Error> where E: Executor<'e, Database = MySql> {
+        let (query_string, args) = self.build().map_err(|e| Error::Configuration(e.message.into()))?;
+        explain_sql(executor, &query_string, &args).await
+    }
+
     pub async fn fetch_count(&self) -> Result<i64, Error> {
-        let pool = POOL.get().unwrap();
-        let build_result = self.build();
-        if let Ok(query_string) = build_result {
-            println!("query string # {}", query_string);
+        on_default_pool(self.fetch_count_with(default_pool()?)).await
+    }
+
+    ///fetch_count inside a transaction
+    pub async fn fetch_count_in(&self, tx: &TransactionHandle) -> Result<i64, Error> {
+        self.fetch_count_with(&mut **tx.lock().await).await
+    }
 
-            let value = sqlx::query(&query_string)
+    ///fetch_count on the given executor: a pool (`&pool`), a connection (`&mut conn`) or a transaction (`&mut *tx`)
+    pub async fn fetch_count_with<'e, E>(&self, executor: E) -> Result<i64, Error> where E: Executor<'e, Database = MySql> {
+        let build_result = self.build();
+        if let Ok((query_string, args)) = build_result {
+            let query = sqlx::query_with(&query_string, to_mysql_arguments(&args)?)
                 .try_map(|row:MySqlRow| {
                     self.convert_to_number(row)
                 })
-                .fetch_one(pool)
-                .await?;
+                .fetch_one(executor);
+            let value = intercept(&query_string, &args, &self.operation, &self.table_name(), |_| 1, query).await?;
             Ok(value)
         }else if let Err(e) = build_result {
             Err(Error::Configuration(e.message.into()))
@@ -1589,7 +2943,7 @@ impl QueryBuilder {
                             }
                         }
                     } else if let Err(err) = value_result {
-                        eprintln!("Error deserializing value for column '{}': {}", column_name, err);
+                        log::warn!("Error deserializing value for column '{}': {}", column_name, err);
                     }
                 }
                 "INT" => {
@@ -1614,7 +2968,7 @@ impl QueryBuilder {
                             }
                         }
                     } else if let Err(err) = value_result {
-                        eprintln!("Error deserializing value for column '{}': {}", column_name, err);
+                        log::warn!("Error deserializing value for column '{}': {}", column_name, err);
                     }
                 }
                 "BIGINT" => {
@@ -1639,7 +2993,7 @@ impl QueryBuilder {
                             }
                         }
                     } else if let Err(err) = value_result {
-                        eprintln!("Error deserializing value for column '{}': {}", column_name, err);
+                        log::warn!("Error deserializing value for column '{}': {}", column_name, err);
                     }
                 }
                 "BIGINT UNSIGNED" => {
@@ -1664,7 +3018,7 @@ impl QueryBuilder {
                             }
                         }
                     } else if let Err(err) = value_result {
-                        eprintln!("Error deserializing value for column '{}': {}", column_name, err);
+                        log::warn!("Error deserializing value for column '{}': {}", column_name, err);
                     }
                 }
                 "DECIMAL" => {
@@ -1699,7 +3053,7 @@ impl QueryBuilder {
                             }
                         }
                     } else if let Err(err) = value_result {
-                        eprintln!("Error deserializing value for column '{}': {}", column_name, err);
+                        log::warn!("Error deserializing value for column '{}': {}", column_name, err);
                     }
                 }
                 "BOOLEAN" => {   //max_size为1时会识别为boolean. MySqlTypeInfo { type: Tiny, flags: ColumnFlags(NOT_NULL | MULTIPLE_KEY), max_size: Some(1) }
@@ -1724,7 +3078,7 @@ impl QueryBuilder {
                             }
                         }
                     } else if let Err(err) = value_result {
-                        eprintln!("Error deserializing value for column '{}': {}", column_name, err);
+                        log::warn!("Error deserializing value for column '{}': {}", column_name, err);
                     }
                 }
                 "TINYINT" => {//max_size>1时会识别为boolean. MySqlTypeInfo { type: Tiny, flags: ColumnFlags(NOT_NULL | MULTIPLE_KEY), max_size: Some(4) }
@@ -1750,7 +3104,7 @@ impl QueryBuilder {
                             }
                         }
                     } else if let Err(err) = value_result {
-                        eprintln!("Error deserializing value for column '{}': {}", column_name, err);
+                        log::warn!("Error deserializing value for column '{}': {}", column_name, err);
                     }
                 }
                 "ENUM" => {
@@ -1774,7 +3128,7 @@ impl QueryBuilder {
                             }
                         }
                     } else if let Err(err) = value_result {
-                        eprintln!("Error deserializing value for column '{}': {}", column_name, err);
+                        log::warn!("Error deserializing value for column '{}': {}", column_name, err);
                     }
                 }
                 "SET" => {
@@ -1804,7 +3158,7 @@ impl QueryBuilder {
                             }
                         }
                     } else if let Err(err) = value_result {
-                        eprintln!("Error deserializing value for column '{}': {}", column_name, err);
+                        log::warn!("Error deserializing value for column '{}': {}", column_name, err);
                     }
                 }
                 "DATETIME" => {
@@ -1831,7 +3185,7 @@ impl QueryBuilder {
                             }
                         }
                     } else if let Err(err) = value_result {
-                        eprintln!("Error deserializing value for column '{}': {}", column_name, err);
+                        log::warn!("Error deserializing value for column '{}': {}", column_name, err);
                     }
                 }
                 "TIMESTAMP" => {
@@ -1856,7 +3210,7 @@ impl QueryBuilder {
                             }
                         }
                     } else if let Err(err) = value_result {
-                        eprintln!("Error deserializing value for column '{}': {}", column_name, err);
+                        log::warn!("Error deserializing value for column '{}': {}", column_name, err);
                     }
                 }
                 "DATE" => {
@@ -1882,7 +3236,7 @@ impl QueryBuilder {
                             }
                         }
                     } else if let Err(err) = value_result {
-                        eprintln!("Error deserializing value for column '{}': {}", column_name, err);
+                        log::warn!("Error deserializing value for column '{}': {}", column_name, err);
                     }
                 }
                 "TIME" => {
@@ -1909,7 +3263,7 @@ impl QueryBuilder {
                             }
                         }
                     } else if let Err(err) = value_result {
-                        eprintln!("Error deserializing value for column '{}': {}", column_name, err);
+                        log::warn!("Error deserializing value for column '{}': {}", column_name, err);
                     }
                 }
                 "CHAR" | "TEXT" | "LONGTEXT" => {
@@ -1934,7 +3288,7 @@ impl QueryBuilder {
                             }
                         }
                     } else if let Err(err) = value_result {
-                        eprintln!("Error deserializing value for column '{}': {}", column_name, err);
+                        log::warn!("Error deserializing value for column '{}': {}", column_name, err);
                     }
                 }
                 "VARBINARY" => {
@@ -2006,60 +3360,208 @@ impl QueryBuilder {
         Ok(count_res)
     }
 
-    fn populate_select_fields_as_string(&self) -> String {
-        self.select_fields.clone().into_iter().map(|field| field.to_string()).collect::<Vec<String>>().join(",")
+    ///the JOIN clauses of select, update and delete
+    fn joins_to_sql(&self, args: &mut Vec<FieldValue>) -> Result<String, QueryBuildError> {
+        if let Some(pending_join) = &self.pending_join {
+            return Err(QueryBuildError::new(BuildErrorType::MissingCondition, format!("please call on() or using() for {} {}", pending_join.join_type.to_string(), pending_join.target_table.name)));
+        }
+        let mut joins = "".to_string();
+        // Traverse joins and generate JOIN statements for each TableJoin
+        for join in self.joins.iter() {
+            joins.push_str(&join.to_sql(args)?);
+        }
+        Ok(joins)
+    }
+
+    ///the ORDER BY ... LIMIT n of a single-table update or delete, MySQL accepts neither on a multi-table one nor an offset
+    fn order_by_limit_to_sql(&self, joins: &str, args: &mut Vec<FieldValue>) -> Result<String, QueryBuildError> {
+        let mut order_by_limit = "".to_string();
+        if self.order_by.is_empty() && self.limit.is_none() {
+            return Ok(order_by_limit);
+        }
+        require(Clause::LimitedWrite, "order by and limit on update or delete")?;
+        if !joins.is_empty() {
+            return Err(QueryBuildError::new(BuildErrorType::OtherError, "order by and limit are not supported on update or delete with joins, use update or delete_all_where without limit".to_string()));
+        }
+        if self.order_by.len() > 0 {
+            order_by_limit = format!(" order by {}", self.order_by.iter()
+                    .map(|field| field.to_sql(args))
+                    .collect::<Vec<String>>()
+                    .join(", "));
+        }
+        if let Some(limit) = &self.limit {
+            if limit.offset != 0 {
+                return Err(QueryBuildError::new(BuildErrorType::OtherError, "limit with an offset is not supported on update or delete".to_string()));
+            }
+            order_by_limit = format!("{} limit {}", order_by_limit, limit.limit);
+        }
+        Ok(order_by_limit)
+    }
+
+    ///ctes referenced by from and joins, each name only once
+    fn collect_ctes(&self) -> Vec<Cte> {
+        let mut ctes: Vec<Cte> = vec![];
+        let tables = self.target_table.iter().chain(self.joins.iter().map(|join| &join.target_table));
+        for table in tables {
+            for cte in table.ctes.iter() {
+                if !ctes.iter().any(|it| it.name == cte.name) {
+                    ctes.push(cte.clone());
+                }
+            }
+        }
+        ctes
+    }
+
+    ///the type of a selected field when it is a column of the from or joined tables, None for expressions
+    fn select_field_type(&self, field: &SelectField) -> Option<&'static str> {
+        let field = match field {
+            SelectField::Field(field) => field,
+            _ => return None,
+        };
+        let tables = self.target_table.iter().chain(self.joins.iter().map(|join| &join.target_table));
+        for table in tables {
+            if !field.table.is_empty() && !table.is_referred_by(&field.table) {
+                continue;
+            }
+            if let Some(column) = table.columns.iter().find(|column| column.get_col_name() == field.name) {
+                return Some(column_type_family(column));
+            }
+        }
+        None
+    }
+
+    fn check_insert_select(target_table: &TargetTable, insert_select: &InsertSelect, query: &QueryBuilder) -> Result<(), QueryBuildError> {
+        //the selected fields with their types, select * is expanded to the columns of the table it selects from
+        let selected: Vec<(String, Option<&'static str>)> = if !query.select_fields.is_empty() {
+            query.select_fields.iter().map(|field| (field.to_string(), query.select_field_type(field))).collect()
+        } else {
+            match &query.target_table {
+                Some(table) if query.joins.is_empty() && query.set_operation.is_none() => table.columns.iter().map(|column| (column.get_col_name(), Some(column_type_family(column)))).collect(),
+                _ => return Err(QueryBuildError::new(BuildErrorType::OtherError, "the select of insert_into must list its fields unless it selects * from a single table".to_string())),
+            }
+        };
+        if selected.len() != insert_select.columns.len() {
+            return Err(QueryBuildError::new(BuildErrorType::OtherError, format!("insert_into has {} columns but the select returns {} fields", insert_select.columns.len(), selected.len())));
+        }
+        for (column, (selected_field, source_type)) in insert_select.columns.iter().zip(selected.iter()) {
+            let column_name = match column {
+                SelectField::Field(field) => field.name.clone(),
+                _ => column.to_string(),
+            };
+            let target_column = target_table.columns.iter().find(|it| it.get_col_name() == column_name);
+            let target_type = match target_column {
+                Some(target_column) => column_type_family(target_column),
+                None => return Err(QueryBuildError::new(BuildErrorType::OtherError, format!("column {} not found in table {}", column_name, target_table.name))),
+            };
+            if let Some(source_type) = source_type {
+                if *source_type != target_type && !(*source_type == "integer" && target_type == "decimal") {
+                    return Err(QueryBuildError::new(BuildErrorType::OtherError, format!("column {} is {} but the selected field {} is {}", column_name, target_type, selected_field, source_type)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn populate_select_fields_as_string(&self, args: &mut Vec<FieldValue>) -> String {
+        self.select_fields.iter().map(|field| field.to_sql(args)).collect::<Vec<String>>().join(",")
+    }
+
+    ///builds the MySQL sql with `?` placeholders, the returned args are bound to them in order
+    pub fn build(&self) -> Result<(String, Vec<FieldValue>),QueryBuildError> {
+        if is_rendering() {
+            //a subquery, build_for translates it with the statement it is part of
+            return self.render();
+        }
+        self.build_for(MySqlDialect)
+    }
+
+    ///builds the sql for the database of dialect, e.g. `build_for(SqliteDialect)`. clauses the dialect can't express,
+    ///such as index hints or a limit on delete, are an error instead of sql the database would reject
+    pub fn build_for<D: Dialect + 'static>(&self, dialect: D) -> Result<(String, Vec<FieldValue>),QueryBuildError> {
+        let dialect: Arc<dyn Dialect> = Arc::new(dialect);
+        let (query_string, args) = render_with(dialect.clone(), || self.render())?;
+        Ok((dialect.translate(&query_string), args))
     }
 
-    pub fn build(&self) -> Result<String,QueryBuildError> {
+    //the sql in MySQL form, get_dialect() decides the clauses that differ by more than names and quotes
+    fn render(&self) -> Result<(String, Vec<FieldValue>),QueryBuildError> {
         let mut queryString = "".to_string();
+        let mut args: Vec<FieldValue> = vec![];
+        if self.lock.is_some() {
+            if let Operation::Select = self.operation {} else {
+                return Err(QueryBuildError::new(BuildErrorType::OtherError, "for_update, for_share, nowait and skip_locked are only supported on select".to_string()));
+            }
+            require(Clause::RowLock, "for_update and for_share")?;
+        }
+        if !self.hints.is_empty() {
+            require(Clause::OptimizerHint, "an optimizer hint")?;
+        }
+        if self.target_table.iter().chain(self.joins.iter().map(|join| &join.target_table)).any(|table| !table.index_hints.is_empty()) {
+            require(Clause::IndexHint, "an index hint")?;
+        }
         match self.operation {
             Operation::Select => {
+                let mut select = "select".to_string();
+                if !self.hints.is_empty() {
+                    select = format!("select /*+ {} */", self.hints.iter().map(|hint| hint.to_string()).collect::<Vec<String>>().join(" "));
+                }
                 if !self.select_fields.is_empty() {
                     if self.distinct.is_some() && self.distinct.unwrap() {
-                        queryString = format!("select distinct {}",self.populate_select_fields_as_string());//self.select_fields.join(", ")
+                        queryString = format!("{} distinct {}",select,self.populate_select_fields_as_string(&mut args));//self.select_fields.join(", ")
                     }else {
-                        queryString = format!("select {}",self.populate_select_fields_as_string());//self.select_fields.join(", ")
+                        queryString = format!("{} {}",select,self.populate_select_fields_as_string(&mut args));//self.select_fields.join(", ")
                     }
                 }else {
                     if let Some(true) = self.is_select_all {
-                        queryString = "select *".to_string();
+                        queryString = format!("{} *",select);
                     }else{
                         return Err(QueryBuildError::new(BuildErrorType::MissingFields,"please provide at lease on field for select operation".to_string()));
                     }
                 }
 
-                if self.target_table.is_some() {
-                    queryString = format!("{} from {}",queryString, self.target_table.clone().unwrap().name);
+                if let Some(target_table) = &self.target_table {
+                    queryString = format!("{} from {}",queryString, target_table.name_with_hints());
+                    args.extend(target_table.args.clone());
+                }else if let Some(set_operation) = &self.set_operation {
+                    queryString = format!("{} from {}",queryString, set_operation.to_sql(&mut args)?);
                 }else {
                     return Err(QueryBuildError::new(BuildErrorType::MissingTargetTable,"please provide table name to select from".to_string()));
                 }
-                if !self.joins.is_empty() {
-                    // Traverse joins and generate JOIN statements for each TableJoin
-                    for (i, join) in self.joins.iter().enumerate() {
-                        // Generate JOIN statements based on joinotype
-                        queryString.push_str(&format!(" {} {} ON {} ", join.join_type.to_string(), join.target_table.name, join.clone().condition.unwrap().query));
-                    }
-                }
+                queryString.push_str(&self.joins_to_sql(&mut args)?);
                 if self.conditions.len() > 0 {
-                    queryString = format!("{} where {}",queryString, self.conditions.iter()
-                            .map(|condition| condition.query.clone())
-                            .collect::<Vec<String>>()
-                            .join(" AND "));
+                    queryString = format!("{} where {}",queryString, conditions_to_sql(&self.conditions, &mut args)?);
                 }
                 if self.group_by.len() > 0 {
                     queryString = format!("{} group by {}",queryString, self.group_by.iter()
-                            .map(|str| str.clone().to_string())
+                            .map(|field| field.to_sql(&mut args))
                             .collect::<Vec<String>>()
                             .join(", "));
                 }
+                if self.having.len() > 0 {
+                    queryString = format!("{} having {}",queryString, conditions_to_sql(&self.having, &mut args)?);
+                }
                 if self.order_by.len() > 0 {
                     queryString = format!("{} order by {}",queryString, self.order_by.iter()
-                            .map(|str| str.clone().to_string())
+                            .map(|field| field.to_sql(&mut args))
                             .collect::<Vec<String>>()
                             .join(", "));
                 }
                 if self.limit.is_some() {
-                    queryString = format!("{} limit {}, {}",queryString,self.clone().limit.unwrap().offset,self.clone().limit.unwrap().limit);
+                    queryString = format!("{} {}",queryString,get_dialect().limit_offset(self.clone().limit.unwrap().offset,self.clone().limit.unwrap().limit));
+                }
+                if let Some(lock) = &self.lock {
+                    queryString = format!("{} {}",queryString, lock.to_sql()?);
+                }
+                let ctes = self.collect_ctes();
+                if !ctes.is_empty() {
+                    let mut cte_args: Vec<FieldValue> = vec![];
+                    let mut cte_list: Vec<String> = vec![];
+                    for cte in ctes.iter() {
+                        cte_list.push(cte.to_sql(&mut cte_args)?);
+                    }
+                    let with = if ctes.iter().any(|cte| cte.recursive) {"WITH RECURSIVE"} else {"WITH"};
+                    queryString = format!("{} {} {}", with, cte_list.join(", "), queryString);
+                    args.splice(0..0, cte_args);
                 }
             },
             Operation::Insert => {
@@ -2073,8 +3575,8 @@ impl QueryBuilder {
                 }
                 let mut insert_fields: Vec<String> = Vec::new();
                 let mut insert_values: Vec<String> = Vec::new();
-                construct_upsert_primary_key_value(&target_table.primary_key,&mut insert_fields, &mut insert_values,&mut vec![]);
-                construct_upsert_fields_values(&target_table.columns, &mut insert_fields, &mut insert_values, &mut vec![], target_table.primary_key.iter().map(|it|it.get_col_name()).collect::<Vec<String>>());
+                construct_upsert_primary_key_value(&target_table.primary_key,&mut insert_fields, &mut insert_values, &mut args, &mut vec![]);
+                construct_upsert_fields_values(&target_table.columns, &mut insert_fields, &mut insert_values, &mut args, &mut vec![], &mut vec![], target_table.primary_key.iter().map(|it|it.get_col_name()).collect::<Vec<String>>());
                 //decrypt?
                 queryString = format!("INSERT INTO {} ({}) VALUES ({})", &target_table.name, insert_fields.join(", "), insert_values.join(", "));
             },
@@ -2088,25 +3590,22 @@ impl QueryBuilder {
                     return Err(QueryBuildError::new(BuildErrorType::MissingPrimaryKey, "Primary key not found for Update_By_Key operation".to_string()));
                 }
 
-                let mut primary_key_conditions = Vec::<String>::new();
+                let mut primary_key_conditions = Vec::<Condition>::new();
                 let mut update_fields_values: Vec<String> = Vec::new();
                 if self.update_values.is_empty() {
-                    construct_upsert_fields_values(&target_table.columns, &mut vec![], &mut vec![], &mut update_fields_values, target_table.primary_key.iter().map(|it|it.get_col_name()).collect::<Vec<String>>());
+                    construct_upsert_fields_values(&target_table.columns, &mut vec![], &mut vec![], &mut vec![], &mut update_fields_values, &mut args, target_table.primary_key.iter().map(|it|it.get_col_name()).collect::<Vec<String>>());
                 }else{
                     update_fields_values = self.update_values
                         .iter()
-                        .map(|(field, value)| format!("{} = {}", field.clone().to_string(), value.clone().to_string()))
+                        .map(|(field, value)| format!("{} = {}", set_column(field), value.to_placeholder(&mut args)))
                         .collect();
                 }
-                construct_upsert_primary_key_value(&target_table.primary_key,&mut vec![], &mut vec![], &mut primary_key_conditions);
+                construct_upsert_primary_key_value(&target_table.primary_key,&mut vec![], &mut vec![], &mut vec![], &mut primary_key_conditions);
                 if primary_key_conditions.is_empty() {
                     return Err(QueryBuildError::new(BuildErrorType::MissingCondition, "please provide at least one condition for update operation".to_string()));
                 }
                 //decrypt?
-                queryString = format!("update {} set {} where {}", self.target_table.clone().unwrap().name, update_fields_values.join(", "), primary_key_conditions.iter()
-                    .map(|condition| condition.clone())
-                    .collect::<Vec<String>>()
-                    .join(" AND "));
+                queryString = format!("update {} set {} where {}", target_table.name_with_hints(), update_fields_values.join(", "), conditions_to_sql(&primary_key_conditions, &mut args)?);
             },
             Operation::Update_By_Condition => {
                 if self.target_table.is_none() {
@@ -2118,26 +3617,30 @@ impl QueryBuilder {
                     return Err(QueryBuildError::new(BuildErrorType::MissingCondition, "please provide at least one condition for Update_By_Condtion operation".to_string()));
                 }
 
+                //joined tables come before SET, so do their bind values
+                let joins = self.joins_to_sql(&mut args)?;
+                if !joins.is_empty() {
+                    require(Clause::JoinedWrite, "update with joins")?;
+                }
                 let mut update_fields_values: Vec<String> = Vec::new();
                 if self.update_values.is_empty() {
-                    construct_upsert_fields_values(&target_table.columns, &mut vec![], &mut vec![], &mut update_fields_values, target_table.primary_key.iter().map(|it|it.get_col_name()).collect::<Vec<String>>());
+                    construct_upsert_fields_values(&target_table.columns, &mut vec![], &mut vec![], &mut vec![], &mut update_fields_values, &mut args, target_table.primary_key.iter().map(|it|it.get_col_name()).collect::<Vec<String>>());
                 }else{
                     update_fields_values = self.update_values
                                         .iter()
-                                        .map(|(field, value)| format!("{} = {}", field.clone().to_string(), value.clone().to_string()))
+                                        .map(|(field, value)| format!("{} = {}", set_column(field), value.to_placeholder(&mut args)))
                                         .collect();
                 }
                 
                 // 修复：使用传入的条件而不是主键条件
-                let where_conditions = self.conditions.iter()
-                    .map(|condition| condition.query.clone())
-                    .collect::<Vec<String>>()
-                    .join(" AND ");
+                let where_conditions = conditions_to_sql(&self.conditions, &mut args)?;
                 
-                queryString = format!("update {} set {} where {}", 
-                    self.target_table.clone().unwrap().name, 
+                queryString = format!("update {}{} set {} where {}{}", 
+                    target_table.name_with_hints(), 
+                    joins.trim_end(),
                     update_fields_values.join(", "), 
-                    where_conditions);
+                    where_conditions,
+                    self.order_by_limit_to_sql(&joins, &mut args)?);
             },
             Operation::Insert_Or_Update => {
                 if self.target_table.is_none() {
@@ -2151,11 +3654,48 @@ impl QueryBuilder {
                 let mut insert_fields: Vec<String> = Vec::new();
                 let mut insert_values: Vec<String> = Vec::new();
                 let mut update_fields_values: Vec<String> = Vec::new();
+                let mut update_args: Vec<FieldValue> = Vec::new();
 
-                construct_upsert_primary_key_value(&target_table.primary_key,&mut insert_fields, &mut insert_values, &mut vec![]);
-                construct_upsert_fields_values(&target_table.columns, &mut insert_fields, &mut insert_values, &mut update_fields_values,target_table.primary_key.iter().map(|it|it.get_col_name()).collect::<Vec<String>>());
+                construct_upsert_primary_key_value(&target_table.primary_key,&mut insert_fields, &mut insert_values, &mut args, &mut vec![]);
+                construct_upsert_fields_values(&target_table.columns, &mut insert_fields, &mut insert_values, &mut args, &mut update_fields_values, &mut update_args, target_table.primary_key.iter().map(|it|it.get_col_name()).collect::<Vec<String>>());
+                //values of the update part come after the insert values
+                args.extend(update_args);
+                let primary_key_names = target_table.primary_key.iter().map(|it| quote_name(&it.get_col_name())).collect::<Vec<String>>();
                 //decrypt?
-                queryString = format!("INSERT INTO {} ({}) VALUES ({}) ON DUPLICATE KEY UPDATE {};", &target_table.name, insert_fields.join(", "), insert_values.join(", "), update_fields_values.join(", "));
+                queryString = format!("INSERT INTO {} ({}) VALUES ({}) {};", &target_table.name, insert_fields.join(", "), insert_values.join(", "), get_dialect().upsert_clause(&primary_key_names, &update_fields_values));
+            },
+            Operation::InsertSelect => {
+                if self.target_table.is_none() {
+                    return Err(QueryBuildError::new(BuildErrorType::MissingTargetTable, "please provide table name for insert operation".to_string()));
+                }
+                let target_table = self.target_table.clone().unwrap();
+                let insert_select = self.insert_select.clone().unwrap_or_default();
+                if insert_select.columns.is_empty() {
+                    return Err(QueryBuildError::new(BuildErrorType::MissingFields, "please provide the columns to insert into".to_string()));
+                }
+                let query = match &insert_select.query {
+                    Some(query) => query,
+                    None => return Err(QueryBuildError::new(BuildErrorType::MissingValues, "please provide the select query to insert from".to_string())),
+                };
+                Self::check_insert_select(&target_table, &insert_select, query)?;
+                let (select_string, select_args) = query.build()?;
+                args.extend(select_args);
+                let columns = insert_select.columns.iter().map(|column| match column {
+                    SelectField::Field(field) => quote_name(&field.name),
+                    _ => column.to_string(),
+                }).collect::<Vec<String>>();
+                queryString = format!("INSERT INTO {} ({}) {}", &target_table.name, columns.join(", "), select_string);
+                if insert_select.update_on_duplicate {
+                    let primary_key_names = target_table.primary_key.iter().map(|it| quote_name(&it.get_col_name())).collect::<Vec<String>>();
+                    let dialect = get_dialect();
+                    let update_fields_values = columns.iter()
+                        .filter(|column| !primary_key_names.contains(column))
+                        .map(|column| format!("{} = {}", column, dialect.excluded_value(column)))
+                        .collect::<Vec<String>>();
+                    if !update_fields_values.is_empty() {
+                        queryString = format!("{} {}", queryString, dialect.upsert_clause(&primary_key_names, &update_fields_values));
+                    }
+                }
             },
             Operation::Delete => {
                 if self.target_table.is_none() {
@@ -2164,31 +3704,125 @@ impl QueryBuilder {
                 if self.conditions.len() <= 0 {
                     return Err(QueryBuildError::new(BuildErrorType::MissingCondition, "please provide filters for  delete operation".to_string()));
                 }
-                queryString = format!("delete from {} where {}", self.target_table.clone().unwrap().name, self.conditions.iter()
-                    .map(|condition| condition.query.clone())
-                    .collect::<Vec<String>>()
-                    .join(" AND "));
+                let target_table = self.target_table.clone().unwrap();
+                let target_name = target_table.name.clone();
+                let joins = self.joins_to_sql(&mut args)?;
+                if joins.is_empty() {
+                    //single-table DELETE has no table_reference to put index hints on
+                    if !target_table.index_hints.is_empty() {
+                        return Err(QueryBuildError::new(BuildErrorType::OtherError, "index hints are only supported on delete with joins".to_string()));
+                    }
+                    queryString = format!("delete from {} where ", target_name);
+                }else{
+                    require(Clause::JoinedWrite, "delete with joins")?;
+                    //multi-table delete only removes rows of the target table, named by its alias if it has one
+                    let lower_name = target_name.to_lowercase();
+                    let delete_name = match lower_name.rfind(" as ") {
+                        Some(index) => target_name[index + 4..].trim().to_string(),
+                        None => target_name.clone(),
+                    };
+                    queryString = format!("delete {} from {}{} where ", delete_name, target_table.name_with_hints(), joins.trim_end());
+                }
+                queryString.push_str(&conditions_to_sql(&self.conditions, &mut args)?);
+                queryString.push_str(&self.order_by_limit_to_sql(&joins, &mut args)?);
             },
             _ => {
                 return Err(QueryBuildError::new(BuildErrorType::MissingOperation,"please provide one of these operation Select, Insert, Update, Delete, Insert_Or_Update".to_string()));
             }
         }
+        if !self.returning.is_empty() {
+            if let Operation::Select = self.operation {
+                return Err(QueryBuildError::new(BuildErrorType::OtherError, "returning is only supported on insert, update and delete".to_string()));
+            }
+            let dialect = get_dialect();
+            if !dialect.supports_returning() {
+                return Err(QueryBuildError::new(BuildErrorType::OtherError, format!("returning is not supported by the {} dialect", dialect.name())));
+            }
+            let returning = self.returning.iter().map(|field| field.to_sql(&mut args)).collect::<Vec<String>>().join(", ");
+            queryString = format!("{} RETURNING {}", queryString.trim_end_matches(';'), returning);
+        }
         // println!("buider: {:#?}",self);
         //println!("queryString: {:#?}",queryString);
-        println!("");
-        Ok(queryString.to_string())
+        Ok((queryString.to_string(), args))
     }
 }
 
 pub struct InnerTable {
     pub table_name: String,
+    ///the alias given to as_table, columns of the derived table are qualified with it
+    pub alias: String,
+    ///selected columns by their name in the result set
     pub map_fields: HashMap<String, Varchar>,
+    pub args: Vec<FieldValue>,
+}
+
+///a named subquery rendered in the WITH clause of the query that selects from or joins it
+#[derive(Debug,Clone)]
+pub struct Cte {
+    pub name: String,
+    ///the queries joined by UNION ALL, for a recursive cte the anchor comes first
+    pub parts: Vec<QueryBuilder>,
+    pub recursive: bool,
+}
+
+impl Cte {
+    fn to_sql(&self, args: &mut Vec<FieldValue>) -> Result<String, QueryBuildError> {
+        let mut parts: Vec<String> = vec![];
+        for part in self.parts.iter() {
+            let (query, part_args) = part.build()?;
+            parts.push(query);
+            args.extend(part_args);
+        }
+        Ok(format!("{} AS ({})", quote_name(&self.name), parts.join(" UNION ALL ")))
+    }
+}
+
+///a cte usable as a Table in from and joins, created by with_cte or with_recursive_cte
+#[derive(Debug,Clone)]
+pub struct CteTable {
+    pub name: String,
+    pub cte: Option<Cte>,
+}
+
+impl CteTable {
+    ///a column of the cte qualified with its name
+    pub fn column(&self, name: &str) -> Varchar {
+        Varchar::with_qualified_name(self.name.clone(), name.to_string())
+    }
+}
+
+impl Table for CteTable {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+    fn ctes(&self) -> Vec<Cte> {
+        self.cte.clone().into_iter().collect()
+    }
+    fn all_columns(&self) -> Vec<SqlColumn> {
+        vec![]
+    }
+    fn primary_key(&self) -> Vec<SqlColumn> {
+        vec![]
+    }
+    fn update_primary_key(&mut self, _primary_key: Vec<SqlColumn>) -> () {
+
+    }
+}
+
+impl InnerTable {
+    ///a column of the derived table qualified with its alias, e.g. `t.column("total").gt(10)`
+    pub fn column(&self, name: &str) -> Varchar {
+        self.map_fields.get(name).cloned().unwrap_or(Varchar::with_qualified_name(self.alias.clone(), name.to_string()))
+    }
 }
 
 impl Table for InnerTable {
     fn name(&self) -> String {
         self.table_name.clone()
     }
+    fn args(&self) -> Vec<FieldValue> {
+        self.args.clone()
+    }
     fn all_columns(&self) -> Vec<SqlColumn> {
         vec![]
     }
@@ -2198,4 +3832,445 @@ impl Table for InnerTable {
     fn update_primary_key(&mut self, primary_key: Vec<SqlColumn>) -> () {
 
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use crate::query::statement::{all_of, any_of, case_when, count_all, curdate, delete_all_where, delete_one_where, except, insert_into, intersect, lag, partition_by, row_number, sum, union_all, update, with_cte, with_recursive_cte};
+    use crate::query::test_support::{use_test_encryptor, Account, Device, Event, Item};
+
+    #[test]
+    fn values_are_bound_in_placeholder_order() {
+        let item = Item::columns();
+        let (sql, args) = select(vec![SelectField::from(item.id.clone()), item.name.clone().into()])
+            .from(&item)
+            .where_(item.name.like("a%".into()).and(item.order.like("it's".into())))
+            .build().unwrap();
+        assert_eq!(sql, "select `item`.`id`,`item`.`name` from `item` where (`item`.`name` LIKE ?) AND (`item`.`order` LIKE ?)");
+        assert!(matches!(args.as_slice(), [FieldValue::String(first), FieldValue::String(second)] if first == "a%" && second == "it's"));
+    }
+
+    #[test]
+    fn having_renders_after_group_by_and_binds_after_where() {
+        let item = Item::columns();
+        let (sql, args) = select(vec![SelectField::from(item.name.clone()), count_all().into()])
+            .from(&item)
+            .where_(item.id.gt(0))
+            .group_by(vec![item.name.clone()])
+            .having(count_all().gt(1i64))
+            .having(sum(item.id.clone()).le(10i64).or(sum(item.id.clone()).is_null()))
+            .limit(5)
+            .build().unwrap();
+        assert_eq!(sql, "select `item`.`name`,count(*) from `item` where `item`.`id` > ? group by `item`.`name` having count(*) > ? AND ((sum(`item`.`id`) <= ?) OR (sum(`item`.`id`) IS NULL)) limit 0, 5");
+        assert!(matches!(args.as_slice(), [FieldValue::I32(0), FieldValue::I64(1), FieldValue::I64(10)]));
+    }
+
+    #[test]
+    fn right_cross_and_using_joins() {
+        let (a, b, c) = (Item::aliased("a"), Item::aliased("b"), Item::aliased("c"));
+        let (sql, _) = select(vec![a.id.clone(), b.id.clone()])
+            .from(&a)
+            .right_join(&b).on(b.id.equal(a.id.clone()))
+            .left_join(&c).using(vec![c.name.clone()])
+            .cross_join(&Item::columns())
+            .build().unwrap();
+        assert_eq!(sql, "select `a`.`id`,`b`.`id` from `item` as `a` RIGHT JOIN `item` as `b` ON `b`.`id` = `a`.`id`  LEFT JOIN `item` as `c` USING (`name`)  CROSS JOIN `item` ");
+    }
+
+    #[test]
+    fn derived_tables_are_joined_with_their_alias_and_args() {
+        let item = Item::columns();
+        let totals = select(vec![SelectField::from(item.name.clone()), count_all().as_("total").into()])
+            .from(&item)
+            .where_(item.id.gt(5))
+            .group_by(vec![item.name.clone()])
+            .as_table("t");
+        let (sql, args) = select(vec![item.id.clone()])
+            .from(&item)
+            .inner_join(&totals).on(totals.column("name").equal(item.name.clone()))
+            .where_(item.id.lt(100))
+            .build().unwrap();
+        assert_eq!(sql, "select `item`.`id` from `item` INNER JOIN (select `item`.`name`,count(*) AS `total` from `item` where `item`.`id` > ? group by `item`.`name`) as `t` ON `t`.`name` = `item`.`name`  where `item`.`id` < ?");
+        assert!(matches!(args.as_slice(), [FieldValue::I32(5), FieldValue::I32(100)]));
+    }
+
+    #[test]
+    fn a_join_without_on_is_a_build_error() {
+        let (a, b) = (Item::aliased("a"), Item::aliased("b"));
+        let error = select(vec![a.id.clone()]).from(&a).left_join(&b).build().unwrap_err();
+        assert!(matches!(error.error(), BuildErrorType::MissingCondition));
+        assert_eq!(error.message(), "please call on() or using() for LEFT JOIN `item` as `b`");
+    }
+
+    #[test]
+    fn window_functions_render_their_over_clause() {
+        let item = Item::columns();
+        let (sql, _) = select(vec![
+                SelectField::from(item.name.clone()),
+                row_number().over(partition_by(vec![item.name.clone()]).order_by(vec![item.id.desc()])).as_("rn").into(),
+                sum(item.id.clone()).over(Window::new().order_by(vec![item.id.clone()])).as_("running").into(),
+                lag(item.id.clone(), 1).over(Window::new()).into(),
+            ])
+            .from(&item)
+            .build().unwrap();
+        assert_eq!(sql, "select `item`.`name`,row_number() OVER (PARTITION BY `item`.`name` ORDER BY `item`.`id` desc) AS `rn`,sum(`item`.`id`) OVER (ORDER BY `item`.`id`) AS `running`,lag(`item`.`id`, 1) OVER () from `item`");
+    }
+
+    #[test]
+    fn cte_args_are_bound_before_the_query_using_them() {
+        let item = Item::columns();
+        let recent = with_cte("recent", select(vec![SelectField::from(item.id.clone()), item.name.clone().into()]).from(&item).where_(item.id.gt(10)));
+        let (sql, args) = select(vec![recent.column("name")])
+            .from(&recent)
+            .where_(recent.column("name").like("a%".into()))
+            .build().unwrap();
+        assert_eq!(sql, "WITH `recent` AS (select `item`.`id`,`item`.`name` from `item` where `item`.`id` > ?) select `recent`.`name` from `recent` where `recent`.`name` LIKE ?");
+        assert!(matches!(args.as_slice(), [FieldValue::I32(10), FieldValue::String(name)] if name == "a%"));
+    }
+
+    #[test]
+    fn recursive_cte_binds_the_anchor_then_the_recursive_part() {
+        let item = Item::columns();
+        let child = Item::aliased("child");
+        let anchor = select(vec![SelectField::from(item.id.clone()), item.name.clone().into()]).from(&item).where_(item.id.equal(1));
+        let tree = with_recursive_cte("tree", anchor, |tree| select(vec![SelectField::from(child.id.clone()), child.name.clone().into()])
+            .from(&child)
+            .inner_join(tree).on(child.order.equal(tree.column("name")))
+            .where_(child.id.lt(50)));
+        let (sql, args) = select(vec![tree.column("id")])
+            .from(&tree)
+            .where_(tree.column("name").like("b%".into()))
+            .build().unwrap();
+        assert_eq!(sql, "WITH RECURSIVE `tree` AS (select `item`.`id`,`item`.`name` from `item` where `item`.`id` = ? UNION ALL select `child`.`id`,`child`.`name` from `item` as `child` INNER JOIN `tree` ON `child`.`order` = `tree`.`name`  where `child`.`id` < ?) select `tree`.`id` from `tree` where `tree`.`name` LIKE ?");
+        assert!(matches!(args.as_slice(), [FieldValue::I32(1), FieldValue::I32(50), FieldValue::String(name)] if name == "b%"));
+    }
+
+    #[test]
+    fn set_operations_combine_their_queries_under_an_alias() {
+        let item = Item::columns();
+        let low = select(vec![item.name.clone()]).from(&item).where_(item.id.lt(10));
+        let high = select(vec![item.name.clone()]).from(&item).where_(item.id.gt(90));
+        let (sql, args) = union_all(vec![low.clone(), high.clone()])
+            .alias("u")
+            .order_by(vec![Varchar::with_qualified_name("u".into(), "name".into())])
+            .limit(20)
+            .build().unwrap();
+        assert_eq!(sql, "select * from ((select `item`.`name` from `item` where `item`.`id` < ?) UNION ALL (select `item`.`name` from `item` where `item`.`id` > ?)) as `u` order by `u`.`name` limit 0, 20");
+        assert!(matches!(args.as_slice(), [FieldValue::I32(10), FieldValue::I32(90)]));
+
+        let (sql, _) = intersect(vec![low.clone(), high.clone()]).build().unwrap();
+        assert_eq!(sql, "select * from ((select `item`.`name` from `item` where `item`.`id` < ?) INTERSECT (select `item`.`name` from `item` where `item`.`id` > ?)) as `my_table`");
+        let (sql, _) = except(vec![low, high]).build().unwrap();
+        assert_eq!(sql, "select * from ((select `item`.`name` from `item` where `item`.`id` < ?) EXCEPT (select `item`.`name` from `item` where `item`.`id` > ?)) as `my_table`");
+    }
+
+    #[test]
+    fn set_operations_fail_with_their_queries() {
+        let (a, b) = (Item::aliased("a"), Item::aliased("b"));
+        let error = union_all(vec![]).build().unwrap_err();
+        assert!(matches!(error.error, BuildErrorType::MissingValues));
+        let error = union_all(vec![select(vec![a.id.clone()]).from(&a), select(vec![a.id.clone()]).from(&a).left_join(&b)]).build().unwrap_err();
+        assert!(matches!(error.error, BuildErrorType::MissingCondition));
+    }
+
+    #[test]
+    fn joined_update_binds_the_join_before_set() {
+        let (a, b) = (Item::aliased("a"), Item::aliased("b"));
+        let (sql, args) = update(&a)
+            .inner_join(&b).on(b.id.equal(a.id.clone()).and(b.id.gt(1)))
+            .set_field(a.name.clone(), b.name.clone())
+            .set(a.order.clone(), "moved")
+            .where_(b.id.lt(5))
+            .build().unwrap();
+        assert_eq!(sql, "update `item` as `a` INNER JOIN `item` as `b` ON (`b`.`id` = `a`.`id`) AND (`b`.`id` > ?) set `a`.`name` = `b`.`name`, `a`.`order` = ? where `b`.`id` < ?");
+        assert!(matches!(args.as_slice(), [FieldValue::I32(1), FieldValue::String(order), FieldValue::I32(5)] if order == "moved"));
+    }
+
+    #[test]
+    fn joined_delete_removes_rows_of_the_target_table_only() {
+        let (a, b) = (Item::aliased("a"), Item::aliased("b"));
+        let (sql, args) = delete_all_where(&a, b.id.gt(3)).left_join(&b).on(b.name.equal(a.name.clone())).build().unwrap();
+        assert_eq!(sql, "delete `a` from `item` as `a` LEFT JOIN `item` as `b` ON `b`.`name` = `a`.`name` where `b`.`id` > ?");
+        assert!(matches!(args.as_slice(), [FieldValue::I32(3)]));
+
+        let item = Item::columns();
+        let (sql, _) = delete_all_where(&item, b.id.is_null()).left_join(&b).on(b.name.equal(item.name.clone())).build().unwrap();
+        assert_eq!(sql, "delete `item` from `item` LEFT JOIN `item` as `b` ON `b`.`name` = `item`.`name` where `b`.`id` IS NULL");
+    }
+
+    #[test]
+    fn row_locks_render_on_select_and_are_rejected_elsewhere() {
+        let item = Item::columns();
+        let (sql, _) = select(vec![item.id.clone()]).from(&item).where_(item.id.gt(1)).limit(10).for_update().skip_locked().build().unwrap();
+        assert_eq!(sql, "select `item`.`id` from `item` where `item`.`id` > ? limit 0, 10 for update skip locked");
+        let (sql, _) = select(vec![item.id.clone()]).from(&item).for_share().nowait().build().unwrap();
+        assert_eq!(sql, "select `item`.`id` from `item` for share nowait");
+
+        let error = select(vec![item.id.clone()]).from(&item).nowait().build().unwrap_err();
+        assert_eq!(error.message, "nowait() and skip_locked() need for_update() or for_share()");
+        let error = update(&item).set(item.name.clone(), "a").where_(item.id.equal(1)).for_update().build().unwrap_err();
+        assert_eq!(error.message, "for_update, for_share, nowait and skip_locked are only supported on select");
+        let error = delete_one_where(&item, item.id.equal(1)).for_share().build().unwrap_err();
+        assert_eq!(error.message, "for_update, for_share, nowait and skip_locked are only supported on select");
+    }
+
+    #[tokio::test]
+    async fn fetch_stream_with_yields_the_error_and_ends() {
+        //nothing listens on port 1
+        let pool = MySqlPoolOptions::new().acquire_timeout(std::time::Duration::from_millis(100)).connect_lazy("mysql://root@127.0.0.1:1/rustnq").unwrap();
+        let (a, b) = (Item::aliased("streamed"), Item::aliased("b"));
+
+        let rows = select(vec![a.id.clone()]).from(&a).left_join(&b).fetch_stream_with::<_, serde_json::Value>(&pool).collect::<Vec<_>>().await;
+        assert!(matches!(rows.as_slice(), [Err(Error::Configuration(message))] if message.to_string() == "please call on() or using() for LEFT JOIN `item` as `b`"));
+
+        let query = select(vec![a.id.clone()]).from(&a);
+        let rows = query.fetch_stream_with::<_, serde_json::Value>(&pool).collect::<Vec<_>>().await;
+        assert!(matches!(rows.as_slice(), [Err(_)]));
+        let stats = crate::query::metrics::metrics().get(&crate::query::metrics::fingerprint(&query.build().unwrap().0)).unwrap();
+        assert_eq!((stats.count, stats.error_count), (1, 1));
+    }
+
+    #[test]
+    fn encrypted_values_are_bound_inside_the_encryptor_expression() {
+        use_test_encryptor();
+        let (sql, args) = QueryBuilder::upsert_table_with_value(&Account::row(1, "x\\' or 1=1 -- ")).build().unwrap();
+        assert_eq!(sql, "INSERT INTO `account` (`id`, `secret`) VALUES (?, AES_ENCRYPT(?, 'key')) ON DUPLICATE KEY UPDATE `secret` = AES_ENCRYPT(?, 'key');");
+        assert_eq!(args.len(), 3);
+        assert!(args[1..].iter().all(|arg| matches!(arg, FieldValue::String(value) if value == "x\\' or 1=1 -- ")));
+
+        let secret = Varchar::with_qualified_name("account".into(), "secret".into()).set_encrypted(true);
+        let mut args = vec![];
+        let sql = secret.equal(Varchar::with_value(Some("it's".into()))).to_sql(&mut args).unwrap();
+        assert_eq!(sql, "`account`.`secret` = AES_ENCRYPT(?, 'key')");
+        assert!(matches!(args.as_slice(), [FieldValue::String(value)] if value == "it's"));
+    }
+
+    #[test]
+    fn case_when_binds_its_results() {
+        let item = Item::columns();
+        let (sql, args) = select(vec![case_when(vec![(item.id.gt(1), Some("it's big"))], Some("small")).unwrap()]).from(&item).build().unwrap();
+        assert!(sql.contains("CASE WHEN `item`.`id` > ? THEN ? ELSE ? END"), "{}", sql);
+        assert!(matches!(args.as_slice(), [FieldValue::I32(1), FieldValue::String(then), FieldValue::String(otherwise)] if then == "it's big" && otherwise == "small"), "{:?}", args);
+        assert!(case_when(vec![(Condition::with_args("id = ?".to_string(), vec![]), Some("a"))], None).is_err());
+    }
+
+    #[test]
+    fn invalid_conditions_are_build_errors() {
+        let item = Item::columns();
+        let invalid = vec![
+            Condition::compare(item.id.qualified_name(), "; drop", FieldValue::I32(1)),
+            Condition::new("1 = 1; drop table item".to_string()),
+            Condition::with_args("`id` = ? or `id` = ?".to_string(), vec![FieldValue::I32(1)]),
+            item.id.equal(1).and(Condition::new("delete from item".to_string()).not()),
+            Condition::exists(QueryBuilder::init_with_select_fields(vec![item.id.clone().into()])),
+            item.name.equal(Varchar::with_name_query("name".into(), Some(select(vec![item.name.clone()]).from(&item)))),
+        ];
+        for condition in invalid {
+            let error = select(vec![item.id.clone()]).from(&item).where_(condition).build().unwrap_err();
+            assert!(matches!(error.error, BuildErrorType::InvalidCondition | BuildErrorType::MissingTargetTable), "{}", error.message);
+        }
+        let mut args = vec![];
+        assert_eq!(item.id.equal(1).not().or(Condition::with_args("`item`.`name` = ?".to_string(), vec!["a".into()])).to_sql(&mut args).unwrap(), "(NOT (`item`.`id` = ?)) OR (`item`.`name` = ?)");
+        assert_eq!(args.len(), 2);
+    }
+
+    #[test]
+    fn dates_compare_with_expressions_unbound() {
+        let day = Date::with_qualified_name("event".into(), "day".into());
+        let mut args = vec![];
+        assert_eq!(day.lt_(curdate()).to_sql(&mut args).unwrap(), "`event`.`day` < CURDATE()");
+        assert_eq!(day.lt_(Date::with_qualified_name("other".into(), "day".into())).to_sql(&mut args).unwrap(), "`event`.`day` < `other`.`day`");
+        assert!(args.is_empty());
+    }
+
+    #[test]
+    fn empty_and_absent_filters_match_every_row() {
+        let item = Item::columns();
+        let query = |condition: Condition| select(vec![item.id.clone()]).from(&item).where_(condition).build().unwrap().0;
+        assert_eq!(query(any_of(vec![])), "select `item`.`id` from `item` where 1 = 1");
+        assert_eq!(query(all_of(vec![])), "select `item`.`id` from `item` where 1 = 1");
+        assert_eq!(query(any_of(vec![item.id.equal(1)])), "select `item`.`id` from `item` where `item`.`id` = ?");
+        assert_eq!(query(any_of(vec![item.id.equal(1), item.id.equal(2)])), "select `item`.`id` from `item` where (`item`.`id` = ?) OR (`item`.`id` = ?)");
+        assert_eq!(query(item.id.equal(1).and_opt(None)), "select `item`.`id` from `item` where `item`.`id` = ?");
+
+        let (sql, args) = select(vec![item.id.clone()]).from(&item)
+            .where_opt(None)
+            .where_opt(Some(item.name.equal("a")))
+            .when(false, |query| query.for_update())
+            .when(true, |query| query.limit(1))
+            .build().unwrap();
+        assert_eq!(sql, "select `item`.`id` from `item` where `item`.`name` = ? limit 0, 1");
+        assert_eq!(args.len(), 1);
+    }
+
+    #[test]
+    fn conditions_display_their_values_apart_from_the_sql() {
+        let item = Item::columns();
+        assert_eq!(item.name.equal("a' or '1'='1").to_string(), "`item`.`name` = ? [String(\"a' or '1'='1\")]");
+        assert_eq!(Condition::with_args("id = ?".to_string(), vec![]).to_string(), "<condition id = ? has 1 placeholders but 0 values>");
+    }
+
+    #[test]
+    fn insert_select_checks_the_types_of_aliased_columns() {
+        let source = Item::aliased("i");
+        let target = Item::columns();
+        let rejected = insert_into(&target).columns(vec![SelectField::from(target.id.clone()), target.name.clone().into()])
+            .select(select(vec![SelectField::from(source.name.clone()), source.id.clone().into()]).from(&source))
+            .build();
+        assert!(matches!(rejected, Err(error) if error.message.contains("column id is integer but the selected field `i`.`name` is text")));
+
+        let (sql, _) = insert_into(&target).columns(vec![SelectField::from(target.id.clone()), target.name.clone().into()])
+            .select(select(vec![SelectField::from(source.id.clone()), source.name.clone().into()]).from(&source))
+            .build().unwrap();
+        assert_eq!(sql, "INSERT INTO `item` (`id`, `name`) select `i`.`id`,`i`.`name` from `item` as `i`");
+    }
+
+    #[test]
+    fn insert_select_expands_select_all() {
+        let target = Item::columns();
+        let all_columns = vec![SelectField::from(target.id.clone()), target.name.clone().into(), target.order.clone().into()];
+        assert!(insert_into(&target).columns(all_columns).select(QueryBuilder::select_all_fields().from(&Item::aliased("i"))).build().is_ok());
+        let rejected = insert_into(&target).columns(vec![SelectField::from(target.id.clone())]).select(QueryBuilder::select_all_fields().from(&Item::aliased("i"))).build();
+        assert!(matches!(rejected, Err(error) if error.message == "insert_into has 1 columns but the select returns 3 fields"));
+    }
+
+    #[test]
+    fn date_json_and_enum_columns_are_bound_with_their_type() {
+        let day = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let (sql, args) = QueryBuilder::upsert_table_with_value(&Event::row(1, day, "on", "{\"a\":1}")).build().unwrap();
+        assert_eq!(sql, "INSERT INTO `event` (`id`, `day`, `status`, `payload`) VALUES (?, ?, ?, ?) ON DUPLICATE KEY UPDATE `day` = ?, `status` = ?, `payload` = ?;");
+        assert!(matches!(args.as_slice(), [FieldValue::I32(1), FieldValue::Date(inserted), FieldValue::Enum(status), FieldValue::Json(payload), FieldValue::Date(_), FieldValue::Enum(_), FieldValue::Json(_)]
+            if *inserted == day && status == "on" && payload == "{\"a\":1}"));
+    }
+
+    #[test]
+    fn uuid_columns_are_bound_as_uuid() {
+        let id = "6f1c2b9e-1d2a-4c55-9a8e-0d7c1f2a3b4c";
+        let (sql, args) = QueryBuilder::insert_into_table_with_value(&Device::row(id, "a")).build().unwrap();
+        assert_eq!(sql, "INSERT INTO `device` (`id`, `name`) VALUES (?, ?)");
+        assert!(matches!(args.as_slice(), [FieldValue::Uuid(inserted), FieldValue::String(_)] if inserted == id));
+
+        let (sql, args) = QueryBuilder::update_table_with_value(&Device::row(id, "b")).build().unwrap();
+        assert_eq!(sql, "update `device` set `name` = ? where `id` = ?");
+        assert!(matches!(args.as_slice(), [FieldValue::String(_), FieldValue::Uuid(key)] if key == id));
+
+        let column = Char::with_qualified_name("device".into(), "id".into()).set_uuid(true);
+        let (_, args) = QueryBuilder::select_all_fields().from(&Device::row(id, "a")).where_(column.equal(Char::with_name_value("id".into(), Some(id.into())))).build().unwrap();
+        assert!(matches!(args.as_slice(), [FieldValue::Uuid(value)] if value == id));
+
+        //columns that are not marked keep binding text
+        let column = Char::with_qualified_name("device".into(), "id".into());
+        let (_, args) = QueryBuilder::select_all_fields().from(&Device::row(id, "a")).where_(column.equal(Char::with_name_value("id".into(), Some(id.into())))).build().unwrap();
+        assert!(matches!(args.as_slice(), [FieldValue::String(_)]));
+    }
+
+    #[test]
+    fn insert_many_is_split_to_fit_the_packet_size() {
+        let rows = (1..=3).map(|id| Item::row(id, "name", "order")).collect::<Vec<Item>>();
+        let statements = build_insert_many(&rows, false, 1 << 20).unwrap();
+        assert_eq!(statements.len(), 1);
+        assert_eq!(statements[0].0, "INSERT INTO `item` (`id`, `name`, `order`) VALUES (?, ?, ?), (?, ?, ?), (?, ?, ?)");
+        assert!(matches!(statements[0].1.as_slice(), [FieldValue::I32(1), _, _, FieldValue::I32(2), _, _, FieldValue::I32(3), _, _]));
+
+        //a row larger than the packet still gets a statement of its own
+        let statements = build_insert_many(&rows, true, 1).unwrap();
+        assert_eq!(statements.len(), 3);
+        for (index, (sql, args)) in statements.iter().enumerate() {
+            assert_eq!(sql, "INSERT INTO `item` (`id`, `name`, `order`) VALUES (?, ?, ?) ON DUPLICATE KEY UPDATE `name` = VALUES(`name`), `order` = VALUES(`order`)");
+            assert!(matches!(args.first(), Some(FieldValue::I32(id)) if *id == index as i32 + 1));
+        }
+
+        //"(?, ?, ?), " and the estimated size of its three values
+        let row_size = 11 + 9 + (4 + 9) + (5 + 9);
+        let prefix_size = "INSERT INTO `item` (`id`, `name`, `order`) VALUES ".len();
+        let chunks = |max_packet_size: usize| build_insert_many(&rows, false, max_packet_size).unwrap().iter().map(|(_, args)| args.len() / 3).collect::<Vec<usize>>();
+        assert_eq!(chunks(prefix_size + 2 * row_size), vec![2, 1]);
+        assert_eq!(chunks(prefix_size + 2 * row_size - 1), vec![1, 1, 1]);
+        assert!(build_insert_many::<Item>(&[], false, 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn delete_renders_order_by_and_limit() {
+        let item = Item::columns();
+        let (sql, args) = delete_one_where(&item, item.name.like("a%".into())).order_by(vec![item.id.desc()]).build().unwrap();
+        assert_eq!(sql, "delete from `item` where `item`.`name` LIKE ? order by `item`.`id` desc limit 1");
+        assert_eq!(args.len(), 1);
+    }
+
+    #[test]
+    fn index_hints_render_on_update_and_are_rejected_on_single_table_delete() {
+        let item = Item::columns();
+        let (sql, _) = update(&item).use_index(vec!["idx_name"]).set(&item.name, "b").where_(item.id.equal(1)).build().unwrap();
+        assert_eq!(sql, "update `item` USE INDEX (`idx_name`) set `item`.`name` = ? where `item`.`id` = ?");
+        let (sql, _) = select(vec![item.id.clone()]).from(&item).force_index(vec!["PRIMARY", "idx-name"]).build().unwrap();
+        assert_eq!(sql, "select `item`.`id` from `item` FORCE INDEX (`PRIMARY`, `idx-name`)");
+        let error = delete_one_where(&item, item.id.equal(1)).use_index(vec!["idx_name"]).build().unwrap_err();
+        assert!(matches!(error.error, BuildErrorType::OtherError));
+    }
+
+    #[test]
+    fn seek_keys_use_the_names_columns_are_selected_as() {
+        let item = Item::columns();
+        let created_at = Datetime::with_qualified_name("item".into(), "created_at".into());
+        let keys = seek_keys(&vec![created_at.desc(), item.id.desc(), SelectField::Untyped("total asc".to_string())], &vec![created_at.clone().as_("createdAt").into(), item.id.clone().into()]);
+        assert_eq!(keys.iter().map(|key| key.expression.as_str()).collect::<Vec<&str>>(), vec!["`item`.`created_at`", "`item`.`id`", "total"]);
+        assert_eq!(keys.iter().map(|key| key.column.as_str()).collect::<Vec<&str>>(), vec!["createdAt", "id", "total"]);
+        assert_eq!(keys.iter().map(|key| key.descending).collect::<Vec<bool>>(), vec![true, true, false]);
+    }
+
+    #[test]
+    fn cursors_keep_datetime_and_composite_keys_typed() {
+        let item = Item::columns();
+        let created_at = Datetime::with_qualified_name("item".into(), "created_at".into());
+        let created = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap().and_hms_micro_opt(10, 0, 0, 123456).unwrap();
+        let rows = vec![
+            (json!({"id": 7}), vec![CursorValue::Datetime(created), CursorValue::I64(7)]),
+            (json!({"id": 6}), vec![CursorValue::Datetime(created), CursorValue::I64(6)]),
+        ];
+        let page = cursor_page::<JsonValue>(rows, 1, false, false).unwrap();
+        assert_eq!(page.data, vec![json!({"id": 7})]);
+        assert!(page.prev_cursor.is_none());
+
+        let cursor = PageCursor::decode(page.next_cursor.as_deref().unwrap()).unwrap();
+        assert!(!cursor.before);
+        assert_eq!(cursor.values, vec![CursorValue::Datetime(created), CursorValue::I64(7)]);
+        let values = cursor.values.iter().map(CursorValue::to_field_value).collect::<Result<Vec<FieldValue>, Error>>().unwrap();
+        let keys = seek_keys(&vec![created_at.desc(), item.id.desc()], &vec![]);
+        let mut args = vec![];
+        assert_eq!(seek_condition(&keys, values, false).to_sql(&mut args).unwrap(), "(`item`.`created_at`, `item`.`id`) < (?, ?)");
+        assert!(matches!(args.as_slice(), [FieldValue::Datetime(value), FieldValue::I64(7)] if *value == created));
+    }
+
+    #[test]
+    fn cursors_of_mixed_directions_compare_key_by_key() {
+        let item = Item::columns();
+        let keys = seek_keys(&vec![SelectField::Untyped("price desc".to_string()), item.id.clone().into()], &vec![]);
+        let cursor = PageCursor { before: false, values: vec![CursorValue::Decimal("12.10".to_string()), CursorValue::I64(3)] };
+        let cursor = PageCursor::decode(&cursor.encode()).unwrap();
+        let values = cursor.values.iter().map(CursorValue::to_field_value).collect::<Result<Vec<FieldValue>, Error>>().unwrap();
+        let mut args = vec![];
+        let sql = seek_condition(&keys, values, false).to_sql(&mut args).unwrap();
+        assert_eq!(sql, "((price < ?)) OR ((price = ?) AND (`item`.`id` > ?))");
+        assert!(matches!(args.as_slice(), [FieldValue::Decimal(a), FieldValue::Decimal(b), FieldValue::I64(3)] if a.to_string() == "12.10" && b == a));
+        assert!(PageCursor::decode("not a cursor").is_err());
+    }
+
+    struct Mapped;
+
+    impl RowMappable for Mapped {
+        fn from_row(_row: &MySqlRow) -> Result<Self, Error> {
+            Ok(Mapped)
+        }
+    }
+
+    #[test]
+    fn only_registered_types_are_fetched_through_row_mappable() {
+        assert!(get_row_mapper::<Mapped>().is_none());
+        register_row_mappable::<Mapped>();
+        assert!(get_row_mapper::<Mapped>().is_some());
+        assert!(get_row_mapper::<JsonValue>().is_none());
+        assert!(downcast_row::<Mapped>(Box::new(Mapped)).is_ok());
+        assert!(downcast_row::<JsonValue>(Box::new(Mapped)).is_err());
+    }
+}
@@ -0,0 +1,449 @@
+use std::cell::RefCell;
+use std::sync::Arc;
+use crate::query::builder::placeholder_positions;
+
+///clauses QueryBuilder renders that only some databases have, building them for another dialect is an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Clause {
+    ///order by and limit on update and delete
+    LimitedWrite,
+    ///update and delete with joins, and SET columns qualified with their table
+    JoinedWrite,
+    ///USE/FORCE/IGNORE INDEX
+    IndexHint,
+    ///`/*+ ... */` optimizer hints
+    OptimizerHint,
+    ///for update and for share
+    RowLock,
+}
+
+///the parts of the sql QueryBuilder renders that differ between databases
+pub trait Dialect: Send + Sync {
+    fn name(&self) -> &'static str;
+    ///quotes a table or column name
+    fn quote_identifier(&self, identifier: &str) -> String;
+    ///the limit clause of a select, e.g. `limit 10, 20`
+    fn limit_offset(&self, offset: i32, limit: i32) -> String;
+    ///appended to an INSERT so rows whose key already exists are updated, assignments are `column = expression`
+    fn upsert_clause(&self, conflict_columns: &[String], assignments: &[String]) -> String;
+    ///the value the INSERT tried to write into column, for the assignments of upsert_clause
+    fn excluded_value(&self, column: &str) -> String;
+    fn boolean_literal(&self, value: bool) -> &'static str;
+    ///the operator of a case-insensitive LIKE
+    fn ilike_operator(&self) -> &'static str {
+        "LIKE"
+    }
+    ///whether insert, update and delete can return rows with RETURNING
+    fn supports_returning(&self) -> bool {
+        false
+    }
+    ///turns the `?` placeholders QueryBuilder renders into the ones the database expects
+    fn render_placeholders(&self, sql: &str) -> String {
+        sql.to_string()
+    }
+    ///a call of the MySQL function name, dialects lacking it render their equivalent
+    fn function_call(&self, name: &str, args: &[String]) -> String {
+        format!("{}({})", name, args.join(","))
+    }
+    fn supports(&self, _clause: Clause) -> bool {
+        false
+    }
+    ///turns the MySQL sql QueryBuilder renders into sql of this dialect: backtick identifiers are quoted with
+    ///quote_identifier, MySQL functions are replaced by function_call and the placeholders rendered
+    fn translate(&self, sql: &str) -> String {
+        self.render_placeholders(&translate_mysql(self, sql))
+    }
+}
+
+///backticks, `limit offset, n` and ON DUPLICATE KEY UPDATE
+pub struct MySqlDialect;
+
+impl Dialect for MySqlDialect {
+    fn name(&self) -> &'static str {
+        "mysql"
+    }
+
+    fn quote_identifier(&self, identifier: &str) -> String {
+        format!("`{}`", identifier.replace('`', "``"))
+    }
+
+    fn limit_offset(&self, offset: i32, limit: i32) -> String {
+        format!("limit {}, {}", offset, limit)
+    }
+
+    fn upsert_clause(&self, _conflict_columns: &[String], assignments: &[String]) -> String {
+        format!("ON DUPLICATE KEY UPDATE {}", assignments.join(", "))
+    }
+
+    fn excluded_value(&self, column: &str) -> String {
+        format!("VALUES({})", column)
+    }
+
+    fn boolean_literal(&self, value: bool) -> &'static str {
+        if value { "1" } else { "0" }
+    }
+
+    fn supports(&self, _clause: Clause) -> bool {
+        true
+    }
+
+    fn translate(&self, sql: &str) -> String {
+        sql.to_string()
+    }
+}
+
+///double quotes, `limit n offset o` and ON CONFLICT (..) DO UPDATE, needs SQLite 3.24 or later
+pub struct SqliteDialect;
+
+impl Dialect for SqliteDialect {
+    fn name(&self) -> &'static str {
+        "sqlite"
+    }
+
+    fn quote_identifier(&self, identifier: &str) -> String {
+        format!("\"{}\"", identifier.replace('"', "\"\""))
+    }
+
+    fn limit_offset(&self, offset: i32, limit: i32) -> String {
+        format!("limit {} offset {}", limit, offset)
+    }
+
+    fn upsert_clause(&self, conflict_columns: &[String], assignments: &[String]) -> String {
+        format!("ON CONFLICT ({}) DO UPDATE SET {}", conflict_columns.join(", "), assignments.join(", "))
+    }
+
+    fn excluded_value(&self, column: &str) -> String {
+        format!("excluded.{}", column)
+    }
+
+    fn boolean_literal(&self, value: bool) -> &'static str {
+        if value { "1" } else { "0" }
+    }
+
+    fn supports_returning(&self) -> bool {
+        true
+    }
+
+    fn function_call(&self, name: &str, args: &[String]) -> String {
+        match (name.to_lowercase().as_str(), args) {
+            ("curdate", []) => "date('now')".to_string(),
+            ("now", []) => "datetime('now')".to_string(),
+            ("year", [date]) => format!("cast(strftime('%Y', {}) as integer)", date),
+            ("month", [date]) => format!("cast(strftime('%m', {}) as integer)", date),
+            ("concat", _) => format!("({})", args.join(" || ")),
+            //MySQL concatenates the arguments of group_concat, SQLite takes the second one as the separator
+            ("group_concat", [_, _, ..]) => format!("group_concat({})", args.join(" || ")),
+            ("rand", []) => "random()".to_string(),
+            ("lcase", _) => format!("lower({})", args.join(",")),
+            ("ucase", _) => format!("upper({})", args.join(",")),
+            ("char_length", _) => format!("length({})", args.join(",")),
+            ("substring", _) => format!("substr({})", args.join(",")),
+            _ => format!("{}({})", name, args.join(",")),
+        }
+    }
+}
+
+///double quotes, `$n` placeholders, ON CONFLICT (..) DO UPDATE, RETURNING and ILIKE
+pub struct PostgresDialect;
+
+impl Dialect for PostgresDialect {
+    fn name(&self) -> &'static str {
+        "postgres"
+    }
+
+    fn quote_identifier(&self, identifier: &str) -> String {
+        format!("\"{}\"", identifier.replace('"', "\"\""))
+    }
+
+    fn limit_offset(&self, offset: i32, limit: i32) -> String {
+        format!("limit {} offset {}", limit, offset)
+    }
+
+    fn upsert_clause(&self, conflict_columns: &[String], assignments: &[String]) -> String {
+        format!("ON CONFLICT ({}) DO UPDATE SET {}", conflict_columns.join(", "), assignments.join(", "))
+    }
+
+    fn excluded_value(&self, column: &str) -> String {
+        format!("EXCLUDED.{}", column)
+    }
+
+    fn boolean_literal(&self, value: bool) -> &'static str {
+        if value { "true" } else { "false" }
+    }
+
+    fn ilike_operator(&self) -> &'static str {
+        "ILIKE"
+    }
+
+    fn supports(&self, clause: Clause) -> bool {
+        clause == Clause::RowLock
+    }
+
+    fn supports_returning(&self) -> bool {
+        true
+    }
+
+    fn render_placeholders(&self, sql: &str) -> String {
+        let mut rendered = String::with_capacity(sql.len() + 8);
+        let mut last = 0;
+        for (index, position) in placeholder_positions(sql).into_iter().enumerate() {
+            rendered.push_str(&sql[last..position]);
+            rendered.push_str(&format!("${}", index + 1));
+            last = position + 1;
+        }
+        rendered.push_str(&sql[last..]);
+        rendered
+    }
+
+    fn function_call(&self, name: &str, args: &[String]) -> String {
+        match (name.to_lowercase().as_str(), args) {
+            ("curdate", []) => "current_date".to_string(),
+            ("now", []) => "now()".to_string(),
+            ("year", [date]) => format!("cast(extract(year from {}) as integer)", date),
+            ("month", [date]) => format!("cast(extract(month from {}) as integer)", date),
+            ("date", [date]) => format!("cast({} as date)", date),
+            ("group_concat", _) => format!("string_agg(concat({}), ',')", args.join(",")),
+            ("rand", []) => "random()".to_string(),
+            ("lcase", _) => format!("lower({})", args.join(",")),
+            ("ucase", _) => format!("upper({})", args.join(",")),
+            ("substring", _) => format!("substr({})", args.join(",")),
+            _ => format!("{}({})", name, args.join(",")),
+        }
+    }
+}
+
+thread_local! {
+    //the dialect build_for renders the statement for, the subqueries built meanwhile are rendered for it too
+    static RENDERING: RefCell<Option<Arc<dyn Dialect>>> = const { RefCell::new(None) };
+}
+
+///the dialect sql is rendered for: the one given to build_for while it builds, MySqlDialect otherwise
+pub fn get_dialect() -> Arc<dyn Dialect> {
+    RENDERING.with(|rendering| rendering.borrow().clone()).unwrap_or_else(|| Arc::new(MySqlDialect))
+}
+
+pub(crate) fn is_rendering() -> bool {
+    RENDERING.with(|rendering| rendering.borrow().is_some())
+}
+
+///runs render with get_dialect() returning dialect
+pub(crate) fn render_with<R>(dialect: Arc<dyn Dialect>, render: impl FnOnce() -> R) -> R {
+    struct Restore(Option<Arc<dyn Dialect>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            RENDERING.with(|rendering| *rendering.borrow_mut() = self.0.take());
+        }
+    }
+    let _restore = Restore(RENDERING.with(|rendering| rendering.borrow_mut().replace(dialect)));
+    render()
+}
+
+//the MySQL functions the dialects have another name or syntax for
+const MYSQL_FUNCTIONS: [&str; 12] = ["curdate", "now", "year", "month", "date", "concat", "group_concat", "rand", "lcase", "ucase", "char_length", "substring"];
+
+//index of the quote closing the quoted text starting at start, a doubled quote is part of the text
+fn closing_quote(chars: &[char], start: usize) -> usize {
+    let quote = chars[start];
+    let mut i = start + 1;
+    while i < chars.len() {
+        if chars[i] == '\\' && quote != '`' {
+            i += 2;
+            continue;
+        }
+        if chars[i] == quote {
+            if i + 1 < chars.len() && chars[i + 1] == quote {
+                i += 2;
+                continue;
+            }
+            return i;
+        }
+        i += 1;
+    }
+    chars.len() - 1
+}
+
+//the arguments of the call whose `(` is at open, and the index of its `)`
+fn call_arguments(chars: &[char], open: usize) -> (Vec<String>, usize) {
+    let mut args = vec![];
+    let mut depth = 0;
+    let mut start = open + 1;
+    let mut i = open;
+    while i < chars.len() {
+        match chars[i] {
+            '\'' | '"' | '`' => i = closing_quote(chars, i),
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            ',' if depth == 1 => {
+                args.push(chars[start..i].iter().collect::<String>());
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    let last = chars[start..i.min(chars.len())].iter().collect::<String>();
+    if !last.trim().is_empty() || !args.is_empty() {
+        args.push(last);
+    }
+    (args, i)
+}
+
+fn translate_mysql<D: Dialect + ?Sized>(dialect: &D, sql: &str) -> String {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut translated = String::with_capacity(sql.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\'' || c == '"' {
+            let end = closing_quote(&chars, i);
+            translated.extend(&chars[i..=end]);
+            i = end + 1;
+        }else if c == '`' {
+            let end = closing_quote(&chars, i);
+            let identifier = chars[i + 1..end.max(i + 1)].iter().collect::<String>().replace("``", "`");
+            translated.push_str(&dialect.quote_identifier(&identifier));
+            i = end + 1;
+        }else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word = chars[start..i].iter().collect::<String>();
+            let is_call = i < chars.len() && chars[i] == '(' && !translated.ends_with('.');
+            if is_call && MYSQL_FUNCTIONS.contains(&word.to_lowercase().as_str()) {
+                let (args, close) = call_arguments(&chars, i);
+                let args = args.iter().map(|arg| translate_mysql(dialect, arg.trim())).collect::<Vec<String>>();
+                translated.push_str(&dialect.function_call(&word, &args));
+                i = close + 1;
+            }else {
+                translated.push_str(&word);
+            }
+        }else {
+            translated.push(c);
+            i += 1;
+        }
+    }
+    translated
+}
+
+//a bare table or column name, anything else (expressions, `*`, numbers, names already quoted) is left as it is
+fn is_plain_identifier(name: &str) -> bool {
+    !name.is_empty() && !name.chars().all(|c| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$' || c == '-')
+}
+
+///quotes a column or alias with backticks, expressions such as `count(*)` are returned unchanged.
+///names are kept in the MySQL form, build_for quotes them for its dialect
+pub fn quote_name(name: &str) -> String {
+    if is_plain_identifier(name) {MySqlDialect.quote_identifier(name)} else {name.to_string()}
+}
+
+///`table.name` with both parts quoted, a table like `db.table` is quoted part by part
+pub fn qualify_name(table: &str, name: &str) -> String {
+    let table = table.split('.').map(quote_name).collect::<Vec<String>>().join(".");
+    format!("{}.{}", table, quote_name(name))
+}
+
+///quotes a table reference: `item`, `db.item`, `item as i` or `(select ..) as t`, a derived table keeps its query and only the alias is quoted
+pub fn quote_table_name(table_name: &str) -> String {
+    let table_name = table_name.trim();
+    let (table, alias) = match table_name.to_lowercase().rfind(" as ") {
+        Some(index) if is_plain_identifier(table_name[index + 4..].trim()) => (table_name[..index].trim(), Some(table_name[index + 4..].trim())),
+        _ => (table_name, None),
+    };
+    let table = if table.split('.').all(is_plain_identifier) {
+        table.split('.').map(quote_name).collect::<Vec<String>>().join(".")
+    } else {
+        table.to_string()
+    };
+    match alias {
+        Some(alias) => format!("{} as {}", table, quote_name(alias)),
+        None => table,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::builder::{BuildErrorType, OptimizerHint, SelectField};
+    use crate::query::select;
+    use crate::query::statement::{concat, curdate, delete_one_where, update, year};
+    use crate::query::test_support::Item;
+
+    #[test]
+    fn identifiers_are_quoted_for_the_dialect_built_for() {
+        let item = Item::columns();
+        let query = select(vec![SelectField::from(item.id.clone()), item.order.clone().into()]).from(&item).where_(item.name.equal("it's `x`"));
+        assert_eq!(query.build().unwrap().0, "select `item`.`id`,`item`.`order` from `item` where `item`.`name` = ?");
+        assert_eq!(query.build_for(SqliteDialect).unwrap().0, "select \"item\".\"id\",\"item\".\"order\" from \"item\" where \"item\".\"name\" = ?");
+        assert_eq!(query.build_for(PostgresDialect).unwrap().0, "select \"item\".\"id\",\"item\".\"order\" from \"item\" where \"item\".\"name\" = $1");
+        //building for another dialect leaves later builds alone
+        assert_eq!(query.build().unwrap().0, "select `item`.`id`,`item`.`order` from `item` where `item`.`name` = ?");
+    }
+
+    #[test]
+    fn names_are_quoted_unless_they_are_expressions() {
+        assert_eq!(quote_name("order"), "`order`");
+        assert_eq!(quote_name("count(*)"), "count(*)");
+        assert_eq!(quote_name("*"), "*");
+        assert_eq!(quote_name("1"), "1");
+        assert_eq!(qualify_name("shop.item", "order"), "`shop`.`item`.`order`");
+        assert_eq!(quote_table_name("item as i"), "`item` as `i`");
+        assert_eq!(quote_table_name("(select 1) as t"), "(select 1) as `t`");
+        assert_eq!(MySqlDialect.quote_identifier("a`b"), "`a``b`");
+        assert_eq!(SqliteDialect.quote_identifier("a\"b"), "\"a\"\"b\"");
+        assert_eq!(SqliteDialect.translate("select `a``b`, 'it''s `x`' from `shop`.`item` as `i`"), "select \"a`b\", 'it''s `x`' from \"shop\".\"item\" as \"i\"");
+    }
+
+    #[test]
+    fn mysql_functions_are_rewritten_for_the_dialect() {
+        let item = Item::columns();
+        let query = select(vec![SelectField::from(year(curdate())), concat(vec![item.name.clone(), item.order.clone()]).into()]).from(&item);
+        assert_eq!(query.build().unwrap().0, "select YEAR(CURDATE()),concat(`item`.`name`,`item`.`order`) from `item`");
+        assert_eq!(query.build_for(SqliteDialect).unwrap().0, "select cast(strftime('%Y', date('now')) as integer),(\"item\".\"name\" || \"item\".\"order\") from \"item\"");
+        assert_eq!(query.build_for(PostgresDialect).unwrap().0, "select cast(extract(year from current_date) as integer),concat(\"item\".\"name\",\"item\".\"order\") from \"item\"");
+    }
+
+    #[test]
+    fn ilike_is_spelled_the_way_the_dialect_does() {
+        let item = Item::columns();
+        let query = select(vec![item.id.clone()]).from(&item).where_(item.name.ilike("a%".to_string()));
+        assert_eq!(query.build().unwrap().0, "select `item`.`id` from `item` where `item`.`name` LIKE ?");
+        assert_eq!(query.build_for(PostgresDialect).unwrap().0, "select \"item\".\"id\" from \"item\" where \"item\".\"name\" ILIKE $1");
+    }
+
+    #[test]
+    fn clauses_the_dialect_lacks_are_an_error() {
+        let item = Item::columns();
+        let other = Item::aliased("other");
+        let unsupported = vec![
+            delete_one_where(&item, item.id.equal(1)),
+            update(&item).set(&item.name, "b").where_(item.id.equal(1)).limit(1),
+            update(&item).inner_join(&other).on(other.id.equal(item.id.clone())).set(&item.name, "b").where_(item.id.equal(1)),
+            select(vec![item.id.clone()]).from(&item).use_index(vec!["idx_name"]),
+            select(vec![item.id.clone()]).from(&item).hint(OptimizerHint::MaxExecutionTime(1000)),
+        ];
+        for query in &unsupported {
+            assert!(query.build().is_ok());
+            for error in [query.build_for(SqliteDialect).unwrap_err(), query.build_for(PostgresDialect).unwrap_err()] {
+                assert!(matches!(error.error, BuildErrorType::OtherError));
+            }
+        }
+        let locked = select(vec![item.id.clone()]).from(&item).where_(item.id.equal(1)).for_update();
+        assert!(locked.build_for(SqliteDialect).unwrap_err().message.contains("sqlite"));
+        assert_eq!(locked.build_for(PostgresDialect).unwrap().0, "select \"item\".\"id\" from \"item\" where \"item\".\"id\" = $1 for update");
+    }
+
+    #[test]
+    fn set_columns_are_unqualified_without_multi_table_update() {
+        let item = Item::columns();
+        let query = update(&item).set(&item.order, "b").where_(item.id.equal(1));
+        assert_eq!(query.build().unwrap().0, "update `item` set `item`.`order` = ? where `item`.`id` = ?");
+        assert_eq!(query.build_for(SqliteDialect).unwrap().0, "update \"item\" set \"order\" = ? where \"item\".\"id\" = ?");
+    }
+}
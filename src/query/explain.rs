@@ -0,0 +1,57 @@
+use serde_json::Value as JsonValue;
+use sqlx::{Error, Executor, Row};
+use sqlx_mysql::MySql;
+use crate::query::builder::{to_mysql_arguments, FieldValue};
+
+///the plan of `EXPLAIN FORMAT=JSON`, with the parts worth a warning picked out
+#[derive(Debug, Clone)]
+pub struct QueryPlan {
+    pub plan: JsonValue,
+    ///tables read with access_type ALL
+    pub full_table_scans: Vec<String>,
+    pub using_filesort: bool,
+    pub using_temporary_table: bool,
+}
+
+impl QueryPlan {
+    pub fn from_json(plan: JsonValue) -> QueryPlan {
+        let mut query_plan = QueryPlan { plan: JsonValue::Null, full_table_scans: vec![], using_filesort: false, using_temporary_table: false };
+        query_plan.inspect(&plan);
+        query_plan.plan = plan;
+        query_plan
+    }
+
+    ///true when the plan has a full table scan or a filesort
+    pub fn has_warnings(&self) -> bool {
+        !self.full_table_scans.is_empty() || self.using_filesort
+    }
+
+    fn inspect(&mut self, node: &JsonValue) {
+        match node {
+            JsonValue::Object(object) => {
+                if object.get("access_type").and_then(|value| value.as_str()) == Some("ALL") {
+                    let table_name = object.get("table_name").and_then(|value| value.as_str()).unwrap_or("");
+                    self.full_table_scans.push(table_name.to_string());
+                }
+                if object.get("using_filesort").and_then(|value| value.as_bool()) == Some(true) {
+                    self.using_filesort = true;
+                }
+                if object.get("using_temporary_table").and_then(|value| value.as_bool()) == Some(true) {
+                    self.using_temporary_table = true;
+                }
+                object.values().for_each(|value| self.inspect(value));
+            }
+            JsonValue::Array(array) => array.iter().for_each(|value| self.inspect(value)),
+            _ => {}
+        }
+    }
+}
+
+///runs `EXPLAIN FORMAT=JSON` for sql with its bound values
+pub async fn explain_sql<'e, E>(executor: E, sql: &str, args: &[FieldValue]) -> Result<QueryPlan, Error> where E: Executor<'e, Database = MySql> {
+    let explain_query_string = format!("EXPLAIN FORMAT=JSON {}", sql);
+    let row = sqlx::query_with(&explain_query_string, to_mysql_arguments(args)?).fetch_one(executor).await?;
+    let plan: String = row.try_get_unchecked(0)?;
+    let plan = serde_json::from_str::<JsonValue>(&plan).map_err(|e| Error::Decode(e.into()))?;
+    Ok(QueryPlan::from_json(plan))
+}
@@ -0,0 +1,240 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+use std::sync::RwLock;
+use std::time::Duration;
+use once_cell::sync::Lazy;
+
+///latencies kept per fingerprint for the quantiles, older samples are dropped first
+const LATENCY_SAMPLES: usize = 1024;
+
+///statistics of the statements sharing a fingerprint
+#[derive(Debug, Clone, Default)]
+pub struct QueryStats {
+    pub count: u64,
+    pub error_count: u64,
+    pub total_latency: Duration,
+    latencies: VecDeque<Duration>,
+}
+
+impl QueryStats {
+    fn record(&mut self, latency: Duration, is_error: bool) {
+        self.count += 1;
+        if is_error {
+            self.error_count += 1;
+        }
+        self.total_latency += latency;
+        if self.latencies.len() == LATENCY_SAMPLES {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back(latency);
+    }
+
+    ///latency at quantile q (0.0..=1.0) over the recent samples
+    pub fn quantile(&self, q: f64) -> Duration {
+        if self.latencies.is_empty() {
+            return Duration::ZERO;
+        }
+        let mut sorted: Vec<Duration> = self.latencies.iter().cloned().collect();
+        sorted.sort();
+        let index = ((sorted.len() - 1) as f64 * q.clamp(0.0, 1.0)).round() as usize;
+        sorted[index]
+    }
+
+    pub fn p50(&self) -> Duration {
+        self.quantile(0.5)
+    }
+
+    pub fn p99(&self) -> Duration {
+        self.quantile(0.99)
+    }
+}
+
+///in-process statistics of executed statements keyed by sql fingerprint, see `metrics()`
+#[derive(Default)]
+pub struct MetricsRegistry {
+    stats: RwLock<HashMap<String, QueryStats>>,
+}
+
+impl MetricsRegistry {
+    pub fn record(&self, fingerprint: &str, latency: Duration, is_error: bool) {
+        let mut stats = self.stats.write().expect("Failed to acquire write lock.");
+        stats.entry(fingerprint.to_string()).or_default().record(latency, is_error);
+    }
+
+    pub fn get(&self, fingerprint: &str) -> Option<QueryStats> {
+        self.stats.read().expect("Failed to acquire read lock.").get(fingerprint).cloned()
+    }
+
+    pub fn snapshot(&self) -> HashMap<String, QueryStats> {
+        self.stats.read().expect("Failed to acquire read lock.").clone()
+    }
+
+    pub fn reset(&self) {
+        self.stats.write().expect("Failed to acquire write lock.").clear();
+    }
+
+    ///dumps the registry in the prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let stats = self.stats.read().expect("Failed to acquire read lock.");
+        let mut fingerprints: Vec<&String> = stats.keys().collect();
+        fingerprints.sort();
+
+        let mut text = String::new();
+        let _ = writeln!(text, "# HELP rustnq_query_total Number of executed statements.");
+        let _ = writeln!(text, "# TYPE rustnq_query_total counter");
+        for fingerprint in fingerprints.iter() {
+            let _ = writeln!(text, "rustnq_query_total{{fingerprint=\"{}\"}} {}", escape_label_value(fingerprint), stats[*fingerprint].count);
+        }
+        let _ = writeln!(text, "# HELP rustnq_query_errors_total Number of statements that failed.");
+        let _ = writeln!(text, "# TYPE rustnq_query_errors_total counter");
+        for fingerprint in fingerprints.iter() {
+            let _ = writeln!(text, "rustnq_query_errors_total{{fingerprint=\"{}\"}} {}", escape_label_value(fingerprint), stats[*fingerprint].error_count);
+        }
+        let _ = writeln!(text, "# HELP rustnq_query_latency_seconds Latency of executed statements.");
+        let _ = writeln!(text, "# TYPE rustnq_query_latency_seconds summary");
+        for fingerprint in fingerprints.iter() {
+            let stat = &stats[*fingerprint];
+            let label = escape_label_value(fingerprint);
+            let _ = writeln!(text, "rustnq_query_latency_seconds{{fingerprint=\"{}\",quantile=\"0.5\"}} {}", label, stat.p50().as_secs_f64());
+            let _ = writeln!(text, "rustnq_query_latency_seconds{{fingerprint=\"{}\",quantile=\"0.99\"}} {}", label, stat.p99().as_secs_f64());
+            let _ = writeln!(text, "rustnq_query_latency_seconds_sum{{fingerprint=\"{}\"}} {}", label, stat.total_latency.as_secs_f64());
+            let _ = writeln!(text, "rustnq_query_latency_seconds_count{{fingerprint=\"{}\"}} {}", label, stat.count);
+        }
+        text
+    }
+}
+
+pub static METRICS: Lazy<MetricsRegistry> = Lazy::new(MetricsRegistry::default);
+
+///the registry every statement executed by QueryBuilder is recorded in
+pub fn metrics() -> &'static MetricsRegistry {
+    &METRICS
+}
+
+///normalizes sql so statements differing only in their values share a fingerprint:
+///literals become ?, lists of placeholders collapse to (...), whitespace is collapsed and keywords are lowercased
+pub fn fingerprint(sql: &str) -> String {
+    let mut normalized = String::with_capacity(sql.len());
+    let chars: Vec<char> = sql.chars().collect();
+    //statements of every dialect are recorded, so double quotes are taken as the identifiers of SQLite and PostgreSQL
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\'' {
+            //string literal, '' and \' stay inside it
+            let quote = c;
+            i += 1;
+            while i < chars.len() {
+                if chars[i] == '\\' {
+                    i += 2;
+                    continue;
+                }
+                if chars[i] == quote {
+                    if i + 1 < chars.len() && chars[i + 1] == quote {
+                        i += 2;
+                        continue;
+                    }
+                    break;
+                }
+                i += 1;
+            }
+            normalized.push('?');
+            i += 1;
+        }else if c == '`' || c == '"' {
+            //quoted identifier, kept as is
+            normalized.push(c);
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                normalized.push(chars[i]);
+                i += 1;
+            }
+            if i < chars.len() {
+                normalized.push(c);
+            }
+            i += 1;
+        }else if c.is_ascii_digit() && !normalized.chars().last().is_some_and(|last| last.is_alphanumeric() || last == '_') {
+            //number not being part of an identifier such as t1
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            normalized.push('?');
+        }else if c.is_whitespace() {
+            while i < chars.len() && chars[i].is_whitespace() {
+                i += 1;
+            }
+            if !normalized.is_empty() {
+                normalized.push(' ');
+            }
+        }else {
+            normalized.push(c.to_ascii_lowercase());
+            i += 1;
+        }
+    }
+    collapse_placeholder_lists(normalized.trim_end())
+}
+
+//(?, ?, ?) => (...) so in lists and multi-row values of any length share a fingerprint
+fn collapse_placeholder_lists(sql: &str) -> String {
+    let mut collapsed = String::with_capacity(sql.len());
+    let mut rest = sql;
+    while let Some(start) = rest.find('(') {
+        collapsed.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find(')') {
+            Some(end) if !after[..end].is_empty() && after[..end].split(',').all(|item| item.trim() == "?") => {
+                collapsed.push_str("(...)");
+                rest = &after[end + 1..];
+            }
+            _ => {
+                collapsed.push('(');
+                rest = after;
+            }
+        }
+    }
+    collapsed.push_str(rest);
+    //values (...), (...) of a multi-row insert
+    while collapsed.contains("(...), (...)") {
+        collapsed = collapsed.replace("(...), (...)", "(...)");
+    }
+    while collapsed.contains("(...),(...)") {
+        collapsed = collapsed.replace("(...),(...)", "(...)");
+    }
+    collapsed
+}
+
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statements_differing_in_values_share_a_fingerprint() {
+        let first = fingerprint("SELECT `t1`.`id` FROM `t1`  WHERE `t1`.`name` = 'it''s' AND `t1`.`id` IN (1, 2, 3) LIMIT 10");
+        let second = fingerprint("select `t1`.`id` from `t1` where `t1`.`name` = 'other' and `t1`.`id` in (?, ?) limit 20");
+        assert_eq!(first, "select `t1`.`id` from `t1` where `t1`.`name` = ? and `t1`.`id` in (...) limit ?");
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn quoted_identifiers_keep_their_case_and_digits() {
+        assert_eq!(fingerprint("SELECT \"Item\".\"col1\" FROM \"Item\" WHERE \"Item\".\"id\" = $1"), "select \"Item\".\"col1\" from \"Item\" where \"Item\".\"id\" = $?");
+        assert_eq!(fingerprint("INSERT INTO `Item` (`id`) VALUES (?), (?)"), "insert into `Item` (`id`) values (...)");
+    }
+
+    #[test]
+    fn the_registry_counts_statements_and_errors_per_fingerprint() {
+        let registry = MetricsRegistry::default();
+        registry.record("select ?", Duration::from_millis(10), false);
+        registry.record("select ?", Duration::from_millis(30), true);
+        let stats = registry.get("select ?").unwrap();
+        assert_eq!((stats.count, stats.error_count), (2, 1));
+        assert_eq!(stats.total_latency, Duration::from_millis(40));
+        assert_eq!(stats.p99(), Duration::from_millis(30));
+        let text = registry.to_prometheus();
+        assert!(text.contains("rustnq_query_total{fingerprint=\"select ?\"} 2"));
+        assert!(text.contains("rustnq_query_errors_total{fingerprint=\"select ?\"} 1"));
+    }
+}
@@ -2,4 +2,15 @@
 pub mod builder;
 pub mod statement;
 pub use statement::*;
-pub mod pool;
\ No newline at end of file
+pub mod pool;
+pub mod metrics;
+pub mod explain;
+pub mod transaction;
+pub use transaction::*;
+pub mod dialect;
+#[cfg(test)]
+pub(crate) mod test_support;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(feature = "postgres")]
+pub mod postgres;
@@ -4,10 +4,18 @@ use sqlx::Error;
 use url::Url;
 use sqlx_mysql::{MySql, MySqlPool, MySqlPoolOptions};
 use sqlx::pool::PoolConnectionMetadata;
+///the default pool used by execute/fetch/... when no executor is given
 pub static POOL: tokio::sync::OnceCell<MySqlPool> = tokio::sync::OnceCell::const_new();
 
-pub async fn init_pool(url: Url, timezone: Option<i8>) {
-    let pool = MySqlPoolOptions::new()
+///initializes the global POOL, calling it again after a successful initialization is a no-op
+pub async fn init_pool(url: Url, timezone: Option<i8>) -> Result<(), Error> {
+    POOL.get_or_try_init(|| connect_pool(url, timezone)).await?;
+    Ok(())
+}
+
+///creates a standalone pool, e.g. for a second database, pass it to the *_with methods of QueryBuilder
+pub async fn connect_pool(url: Url, timezone: Option<i8>) -> Result<MySqlPool, Error> {
+    MySqlPoolOptions::new()
         .acquire_timeout(std::time::Duration::from_secs(20))
         .after_connect(move |conn,_| {
             Box::pin(async move {
@@ -20,6 +28,30 @@ pub async fn init_pool(url: Url, timezone: Option<i8>) {
             })
         })
         .connect(url.as_str())
-        .await.expect("Failed to connect to database");
-    POOL.set(pool).unwrap();
-}
\ No newline at end of file
+        .await
+}
+
+///the global POOL, or an error if init_pool has not been called
+pub fn default_pool() -> Result<&'static MySqlPool, Error> {
+    POOL.get().ok_or_else(|| Error::Configuration("the default pool is not initialized, call init_pool first".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn init_pool_keeps_the_first_pool() {
+        //a failed initialization leaves the default pool unset
+        let invalid = Url::parse("mysql://root@127.0.0.1:1/rustnq?ssl-mode=invalid").unwrap();
+        assert!(init_pool(invalid, None).await.is_err());
+        assert!(default_pool().is_err());
+
+        //nothing listens on port 1, the pool never connects
+        let unreachable = Url::parse("mysql://root@127.0.0.1:1/rustnq").unwrap();
+        POOL.set(MySqlPoolOptions::new().connect_lazy(unreachable.as_str()).unwrap()).unwrap();
+        let first = default_pool().unwrap() as *const MySqlPool;
+        init_pool(unreachable, Some(8)).await.unwrap();
+        assert_eq!(default_pool().unwrap() as *const MySqlPool, first);
+    }
+}
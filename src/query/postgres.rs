@@ -0,0 +1,139 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+use sqlx::{Arguments, Column, Encode, Error, Executor, Row, Type, TypeInfo, ValueRef};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use tracing::Instrument;
+use sqlx_postgres::{PgArgumentBuffer, PgArguments, PgQueryResult, PgRow, PgTypeInfo, Postgres};
+use sqlx_postgres::types::Oid;
+use crate::configuration::QueryExecution;
+use crate::query::builder::{set_json_column, FieldValue, QueryBuilder};
+use crate::query::dialect::PostgresDialect;
+
+//text bound without a type, the server takes the type of the column it is stored in or compared with, e.g. an enum
+struct Untyped(String);
+
+impl Type<Postgres> for Untyped {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_oid(Oid(0))
+    }
+}
+
+impl Encode<'_, Postgres> for Untyped {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        <&str as Encode<Postgres>>::encode(self.0.as_str(), buf)
+    }
+}
+
+//a uuid given as text, with or without hyphens
+struct UuidText(uuid::Uuid);
+
+impl Type<Postgres> for UuidText {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_oid(Oid(2950))
+    }
+}
+
+impl Encode<'_, Postgres> for UuidText {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        buf.extend_from_slice(self.0.as_bytes());
+        Ok(IsNull::No)
+    }
+}
+
+///collects bind values into PgArguments, in the order of the `$n` placeholders.
+///strings are bound as text, so comparing them with numeric columns needs a cast, enum values take the type of their column
+///and the values of Char columns marked with set_uuid are bound as uuid
+pub(crate) fn to_postgres_arguments(args: &[FieldValue]) -> Result<PgArguments, Error> {
+    let mut arguments = PgArguments::default();
+    for arg in args {
+        let add_result = match arg {
+            FieldValue::Field(field) => return Err(Error::Encode(format!("column {} can not be bound as a value", field.name).into())),
+            FieldValue::String(value) => arguments.add(value.clone()),
+            FieldValue::Bool(value) => arguments.add(*value),
+            FieldValue::I32(value) => arguments.add(*value),
+            FieldValue::I64(value) => arguments.add(*value),
+            FieldValue::U64(value) => arguments.add(i64::try_from(*value).map_err(|e| Error::Encode(e.into()))?),
+            FieldValue::F64(value) => arguments.add(*value),
+            FieldValue::Decimal(value) => arguments.add(*value),
+            FieldValue::Date(value) => arguments.add(*value),
+            FieldValue::Time(value) => arguments.add(*value),
+            FieldValue::Datetime(value) => arguments.add(*value),
+            FieldValue::Bytes(value) => arguments.add(value.clone()),
+            FieldValue::Json(value) => arguments.add(serde_json::from_str::<JsonValue>(value).map_err(|e| Error::Encode(e.into()))?),
+            FieldValue::Enum(value) => arguments.add(Untyped(value.clone())),
+            FieldValue::Uuid(value) => arguments.add(UuidText(uuid::Uuid::parse_str(value).map_err(|e| Error::Encode(e.into()))?)),
+            FieldValue::Null => arguments.add(None::<String>),
+        };
+        add_result.map_err(Error::Encode)?;
+    }
+    Ok(arguments)
+}
+
+///a row as json, named and typed like the rows of fetch: dates and timestamps become epoch milliseconds, numerics f64
+fn postgres_row_to_json(row: PgRow) -> Result<JsonValue, Error> {
+    let mut json_obj = json!({});
+    for (i, column) in row.columns().iter().enumerate() {
+        if row.try_get_raw(i)?.is_null() {
+            set_json_column(&mut json_obj, column.name(), JsonValue::Null);
+            continue;
+        }
+        let value = match column.type_info().name() {
+            "INT2" => row.try_get::<i16, _>(i)?.into(),
+            "INT4" => row.try_get::<i32, _>(i)?.into(),
+            "INT8" => row.try_get::<i64, _>(i)?.into(),
+            "FLOAT4" => row.try_get::<f32, _>(i)?.into(),
+            "FLOAT8" => row.try_get::<f64, _>(i)?.into(),
+            "NUMERIC" => row.try_get::<rust_decimal::Decimal, _>(i)?.to_f64().map_or(JsonValue::Null, JsonValue::from),
+            "BOOL" => row.try_get::<bool, _>(i)?.into(),
+            "TIMESTAMP" => row.try_get::<NaiveDateTime, _>(i)?.and_utc().timestamp_millis().into(),
+            "TIMESTAMPTZ" => row.try_get::<DateTime<Utc>, _>(i)?.timestamp_millis().into(),
+            "DATE" => row.try_get::<NaiveDate, _>(i)?.and_time(NaiveTime::default()).and_utc().timestamp_millis().into(),
+            "TIME" => JsonValue::String(row.try_get::<NaiveTime, _>(i)?.format("%H:%M:%S").to_string()),
+            "JSON" | "JSONB" => row.try_get::<JsonValue, _>(i)?,
+            "BYTEA" => JsonValue::String(hex::encode(row.try_get::<Vec<u8>, _>(i)?)),
+            "UUID" => JsonValue::String(uuid::Uuid::from_slice(row.try_get_raw(i)?.as_bytes().map_err(Error::Decode)?).map_err(|e| Error::Decode(e.into()))?.to_string()),
+            //text types and enums
+            _ => JsonValue::String(row.try_get_unchecked::<String, _>(i)?),
+        };
+        set_json_column(&mut json_obj, column.name(), value);
+    }
+    Ok(json_obj)
+}
+
+///executing on PostgreSQL, statements are built with build_for(PostgresDialect). the slow query check is MySQL only and is skipped
+impl QueryBuilder {
+    ///execute insert/update/delete on a PostgreSQL executor, use fetch_postgres to read the rows of returning()
+    pub async fn execute_postgres<'e, E>(&self, executor: E) -> Result<PgQueryResult, Error> where E: Executor<'e, Database = Postgres> {
+        let (query_string, args) = self.build_for(PostgresDialect).map_err(|e| Error::Configuration(e.message.into()))?;
+        let execution = QueryExecution::start(&query_string, &args, &self.operation, &self.table_name());
+        let result = sqlx::query_with(&query_string, to_postgres_arguments(&args)?).execute(executor).instrument(execution.span.clone()).await;
+        match &result {
+            Ok(query_result) => execution.finish(query_result.rows_affected(), None),
+            Err(error) => execution.finish(0, Some(error)),
+        };
+        result
+    }
+
+    ///fetch on a PostgreSQL executor, rows are converted to json and deserialized into T
+    pub async fn fetch_postgres<'e, E, T: Serialize + for<'de> serde::Deserialize<'de>>(&self, executor: E) -> Result<Vec<T>, Error> where E: Executor<'e, Database = Postgres> {
+        let (query_string, args) = self.build_for(PostgresDialect).map_err(|e| Error::Configuration(e.message.into()))?;
+        let execution = QueryExecution::start(&query_string, &args, &self.operation, &self.table_name());
+        let result = sqlx::query_with(&query_string, to_postgres_arguments(&args)?).fetch_all(executor).instrument(execution.span.clone()).await;
+        let rows = match result {
+            Ok(rows) => {
+                execution.finish(rows.len() as u64, None);
+                rows
+            }
+            Err(error) => {
+                execution.finish(0, Some(&error));
+                return Err(error);
+            }
+        };
+        rows.into_iter()
+            .map(|row| postgres_row_to_json(row).and_then(|json| serde_json::from_value::<T>(json).map_err(|e| Error::Decode(e.into()))))
+            .collect()
+    }
+}
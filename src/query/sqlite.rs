@@ -0,0 +1,144 @@
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+use sqlx::{Arguments, Column, Error, Executor, Row, TypeInfo, ValueRef};
+use tracing::Instrument;
+use sqlx_sqlite::{Sqlite, SqliteArguments, SqliteQueryResult, SqliteRow};
+use crate::configuration::QueryExecution;
+use crate::query::builder::{set_json_column, FieldValue, QueryBuilder};
+use crate::query::dialect::SqliteDialect;
+
+///collects bind values into SqliteArguments, in the order of the `?` placeholders
+pub(crate) fn to_sqlite_arguments(args: &[FieldValue]) -> Result<SqliteArguments<'static>, Error> {
+    let mut arguments = SqliteArguments::default();
+    for arg in args {
+        let add_result = match arg {
+            FieldValue::Field(field) => return Err(Error::Encode(format!("column {} can not be bound as a value", field.name).into())),
+            FieldValue::String(value) | FieldValue::Json(value) | FieldValue::Enum(value) | FieldValue::Uuid(value) => arguments.add(value.clone()),
+            FieldValue::Bool(value) => arguments.add(*value),
+            FieldValue::I32(value) => arguments.add(*value),
+            FieldValue::I64(value) => arguments.add(*value),
+            FieldValue::U64(value) => arguments.add(i64::try_from(*value).map_err(|e| Error::Encode(e.into()))?),
+            FieldValue::F64(value) => arguments.add(*value),
+            //SQLite has no decimal type, its numeric affinity converts the text
+            FieldValue::Decimal(value) => arguments.add(value.to_string()),
+            FieldValue::Date(value) => arguments.add(*value),
+            FieldValue::Time(value) => arguments.add(*value),
+            FieldValue::Datetime(value) => arguments.add(*value),
+            FieldValue::Bytes(value) => arguments.add(value.clone()),
+            FieldValue::Null => arguments.add(None::<String>),
+        };
+        add_result.map_err(Error::Encode)?;
+    }
+    Ok(arguments)
+}
+
+///a row as json, named like the rows of fetch
+fn sqlite_row_to_json(row: SqliteRow) -> Result<JsonValue, Error> {
+    let mut json_obj = json!({});
+    for (i, column) in row.columns().iter().enumerate() {
+        let raw = row.try_get_raw(i)?;
+        let value = if raw.is_null() {
+            JsonValue::Null
+        }else {
+            match raw.type_info().name() {
+                "INTEGER" => row.try_get::<i64, _>(i)?.into(),
+                "REAL" => row.try_get::<f64, _>(i)?.into(),
+                "BLOB" => JsonValue::String(hex::encode(row.try_get::<Vec<u8>, _>(i)?)),
+                _ => JsonValue::String(row.try_get::<String, _>(i)?),
+            }
+        };
+        set_json_column(&mut json_obj, column.name(), value);
+    }
+    Ok(json_obj)
+}
+
+///executing on SQLite, statements are built with build_for(SqliteDialect). the slow query check is MySQL only and is skipped
+impl QueryBuilder {
+    ///execute insert/update/delete on a SQLite executor, e.g. an in-memory pool in tests
+    pub async fn execute_sqlite<'e, E>(&self, executor: E) -> Result<SqliteQueryResult, Error> where E: Executor<'e, Database = Sqlite> {
+        let (query_string, args) = self.build_for(SqliteDialect).map_err(|e| Error::Configuration(e.message.into()))?;
+        let execution = QueryExecution::start(&query_string, &args, &self.operation, &self.table_name());
+        let result = sqlx::query_with(&query_string, to_sqlite_arguments(&args)?).execute(executor).instrument(execution.span.clone()).await;
+        match &result {
+            Ok(query_result) => execution.finish(query_result.rows_affected(), None),
+            Err(error) => execution.finish(0, Some(error)),
+        };
+        result
+    }
+
+    ///fetch on a SQLite executor, rows are converted to json and deserialized into T
+    pub async fn fetch_sqlite<'e, E, T: Serialize + for<'de> serde::Deserialize<'de>>(&self, executor: E) -> Result<Vec<T>, Error> where E: Executor<'e, Database = Sqlite> {
+        let (query_string, args) = self.build_for(SqliteDialect).map_err(|e| Error::Configuration(e.message.into()))?;
+        let execution = QueryExecution::start(&query_string, &args, &self.operation, &self.table_name());
+        let result = sqlx::query_with(&query_string, to_sqlite_arguments(&args)?).fetch_all(executor).instrument(execution.span.clone()).await;
+        let rows = match result {
+            Ok(rows) => {
+                execution.finish(rows.len() as u64, None);
+                rows
+            }
+            Err(error) => {
+                execution.finish(0, Some(&error));
+                return Err(error);
+            }
+        };
+        rows.into_iter()
+            .map(|row| sqlite_row_to_json(row).and_then(|json| serde_json::from_value::<T>(json).map_err(|e| Error::Decode(e.into()))))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use sqlx_sqlite::{SqlitePool, SqlitePoolOptions};
+    use crate::query::builder::SelectField;
+    use crate::query::select;
+    use crate::query::statement::{delete_all_where, delete_one_where, update};
+    use crate::query::test_support::Item;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct ItemRow {
+        id: i64,
+        name: String,
+        order: String,
+    }
+
+    async fn pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        sqlx::query("create table item (id integer primary key, name text not null, \"order\" text not null)").execute(&pool).await.unwrap();
+        pool
+    }
+
+    async fn items(pool: &SqlitePool) -> Vec<ItemRow> {
+        let item = Item::columns();
+        select(vec![SelectField::from(item.id.clone()), item.name.clone().into(), item.order.clone().into()]).from(&item).order_by(vec![item.id.asc()]).fetch_sqlite(pool).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn statements_run_on_sqlite() {
+        let pool = pool().await;
+        for row in [Item::row(1, "a", "first"), Item::row(2, "b", "second")] {
+            QueryBuilder::insert_into_table_with_value(&row).execute_sqlite(&pool).await.unwrap();
+        }
+        QueryBuilder::upsert_table_with_value(&Item::row(2, "b", "it's second")).execute_sqlite(&pool).await.unwrap();
+        let item = Item::columns();
+        update(&item).set(&item.name, "c").where_(item.id.equal(1)).execute_sqlite(&pool).await.unwrap();
+        assert_eq!(items(&pool).await, vec![
+            ItemRow { id: 1, name: "c".to_string(), order: "first".to_string() },
+            ItemRow { id: 2, name: "b".to_string(), order: "it's second".to_string() },
+        ]);
+
+        let deleted = delete_all_where(&item, item.name.ilike("C".to_string())).execute_sqlite(&pool).await.unwrap();
+        assert_eq!(deleted.rows_affected(), 1);
+        assert_eq!(items(&pool).await.iter().map(|row| row.id).collect::<Vec<i64>>(), vec![2]);
+    }
+
+    #[tokio::test]
+    async fn clauses_sqlite_lacks_fail_before_reaching_it() {
+        let pool = pool().await;
+        let item = Item::columns();
+        let error = delete_one_where(&item, item.id.equal(1)).execute_sqlite(&pool).await.unwrap_err();
+        assert!(matches!(error, Error::Configuration(_)));
+    }
+}
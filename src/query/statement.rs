@@ -2,15 +2,18 @@ use std::collections::HashMap;
 use uuid::uuid;
 use crate::mapping::description::{Column, SqlColumn};
 use crate::mapping::description::Table;
-use crate::mapping::column_types::{Bigint, Date, Int};
-use crate::query::builder::{Condition, InnerTable, QueryBuilder, SelectField, TargetTable};
+use crate::mapping::column_types::{Bigint, Date, Decimal, Int};
+use crate::query::builder::{Condition, Cte, CteTable, FieldValue, QueryBuildError, QueryBuilder, SelectField, SetOperator, TargetTable, Window, WindowFunction};
 use serde::Serialize;
 use sqlx::Error;
 use tokio::sync::RwLock;
-use crate::configuration::{get_processors, PROCESSORS};
+use crate::configuration::{get_processors, intercept, on_default_pool, PROCESSORS};
 use crate::mapping::column_types::Varchar;
-use crate::query::builder::construct_upsert_primary_key_value;
+use crate::query::builder::{build_insert_many, construct_upsert_primary_key_value, to_mysql_arguments, Operation};
 use crate::utils::date_sub_unit::DateSubUnit;
+use crate::query::pool::default_pool;
+use crate::query::dialect::{Dialect, MySqlDialect};
+use sqlx_mysql::{MySqlConnection, MySqlQueryResult};
 use std::any::Any;
 
 pub fn select<T: Into<SelectField>>(fields: Vec<T>) -> QueryBuilder{
@@ -39,68 +42,141 @@ pub fn count_distinct<T: Into<SelectField>>(field:T) -> Bigint{
     Bigint::with_name(format!("count(distinct {})", field.into().to_string()))
 }
 
-pub fn case_when<T:Any+ std::fmt::Display>(cases:Vec<(Condition, Option<T>)>, else_value:Option<T>) -> SelectField{
+///CASE WHEN condition THEN value ... ELSE else_value END, the condition values and the results are bound.
+///fails like build() for a condition that does not render, e.g. a raw condition with the wrong number of values
+pub fn case_when<T:Any+ std::fmt::Display>(cases:Vec<(Condition, Option<T>)>, else_value:Option<T>) -> Result<SelectField, QueryBuildError>{
     let mut case = "CASE".to_string();
+    //the results are bound as strings like the quoted literals they replace
+    let mut args = vec![];
 
     // Add WHEN conditions
     for (condition, value) in cases {
+        let condition = condition.to_sql(&mut args)?;
         if let Some(val) = value {
-            case.push_str(&format!(" WHEN {} THEN '{}'", condition, val));
+            case.push_str(&format!(" WHEN {} THEN ?", condition));
+            args.push(FieldValue::String(val.to_string()));
         }else{
-            case.push_str(" WHEN {} THEN null");
+            case.push_str(&format!(" WHEN {} THEN null", condition));
         }
     }
 
     // Add ELSE clause if provided
     if let Some(else_val) = else_value {
-        case.push_str(&format!(" ELSE '{}'", else_val));
+        case.push_str(" ELSE ?");
+        args.push(FieldValue::String(else_val.to_string()));
     }else {
         case.push_str(" ELSE null");
     }
 
     case.push_str(" END");
 
-    SelectField::Untyped(case)
+    Ok(SelectField::Raw{sql: case, args})
 }
 
+pub fn union(sql_list: Vec<QueryBuilder>) -> QueryBuilder{
+    QueryBuilder::init_with_set_operation(SetOperator::Union, sql_list)
+}
+
+///select * from (a union all b ...) as my_table, use alias() to rename it and order_by/limit to sort or page the combined rows
 pub fn union_all(sql_list: Vec<QueryBuilder>) -> QueryBuilder{
-    let mut list: Vec<String> = vec![];
-    for sql in sql_list {
-        let sql_result = sql.build();
-        if let Ok(sql_string) = sql_result {
-            println!("sql string {}", sql_string);
-            list.push(sql_string);
-        }
+    QueryBuilder::init_with_set_operation(SetOperator::UnionAll, sql_list)
+}
+
+///needs MySQL 8.0.31 or later
+pub fn intersect(sql_list: Vec<QueryBuilder>) -> QueryBuilder{
+    QueryBuilder::init_with_set_operation(SetOperator::Intersect, sql_list)
+}
+
+///needs MySQL 8.0.31 or later
+pub fn except(sql_list: Vec<QueryBuilder>) -> QueryBuilder{
+    QueryBuilder::init_with_set_operation(SetOperator::Except, sql_list)
+}
+
+///names query once so it can be used as a table, e.g. `let recent = with_cte("recent", qb); select_all().from(&recent)`
+pub fn with_cte(name:&str, query:QueryBuilder) -> CteTable{
+    CteTable {
+        name: name.to_string(),
+        cte: Some(Cte { name: name.to_string(), parts: vec![query], recursive: false }),
+    }
+}
+
+///a WITH RECURSIVE cte: anchor UNION ALL the query returned by recursive, which gets the cte itself to join on.
+///e.g. walking a category tree `with_recursive_cte("tree", roots, |tree| select(..).from(&category).inner_join(tree).on(..))`
+pub fn with_recursive_cte<F>(name:&str, anchor:QueryBuilder, recursive:F) -> CteTable where F: FnOnce(&CteTable) -> QueryBuilder{
+    let reference = CteTable { name: name.to_string(), cte: None };
+    let recursive_part = recursive(&reference);
+    CteTable {
+        name: name.to_string(),
+        cte: Some(Cte { name: name.to_string(), parts: vec![anchor, recursive_part], recursive: true }),
     }
-    let table = InnerTable{
-        table_name: format!("({}) as my_table", list.join(" union all ")),
-        map_fields: Default::default(),
-    };
-    QueryBuilder::init_with_select_all_fields(&table)
 }
 
 pub fn exists(sql:QueryBuilder) -> Condition{
-    Condition::new(format!("exists ({})", sql.build().unwrap_or_default()))
+    Condition::exists(sql)
 }
 
 pub fn not_exists(sql:QueryBuilder) -> Condition{
-    Condition::new(format!("not exists ({})", sql.build().unwrap_or_default()))
+    Condition::not_exists(sql)
+}
+
+pub fn all_of(conditions:Vec<Condition>) -> Condition{
+    Condition::all_of(conditions)
+}
+
+pub fn any_of(conditions:Vec<Condition>) -> Condition{
+    Condition::any_of(conditions)
+}
+
+pub fn not(condition:Condition) -> Condition{
+    condition.not()
 }
 
 pub fn max<T: Into<SelectField>>(field:T) -> Varchar{
     Varchar::with_name(format!("max({})", field.into().to_string()))
 }
 
+pub fn avg<T: Into<SelectField>>(field:T) -> Decimal{
+    Decimal::with_name(format!("avg({})", field.into().to_string()))
+}
+
+///the window of a window function, e.g. `row_number().over(partition_by(vec![..]).order_by(vec![..]))`
+pub fn partition_by<T: Into<SelectField>>(fields: Vec<T>) -> Window{
+    Window::new().partition_by(fields)
+}
+
+pub fn row_number() -> WindowFunction<Bigint>{
+    WindowFunction::new("row_number()".to_string(), Bigint::with_name)
+}
+
+pub fn rank() -> WindowFunction<Bigint>{
+    WindowFunction::new("rank()".to_string(), Bigint::with_name)
+}
+
+pub fn dense_rank() -> WindowFunction<Bigint>{
+    WindowFunction::new("dense_rank()".to_string(), Bigint::with_name)
+}
+
+///value of field offset rows before the current row in the window
+pub fn lag<T: Into<SelectField>>(field:T, offset:u32) -> WindowFunction<Varchar>{
+    WindowFunction::new(format!("lag({}, {})", field.into().to_string(), offset), Varchar::with_name)
+}
+
+///value of field offset rows after the current row in the window
+pub fn lead<T: Into<SelectField>>(field:T, offset:u32) -> WindowFunction<Varchar>{
+    WindowFunction::new(format!("lead({}, {})", field.into().to_string(), offset), Varchar::with_name)
+}
+
+///MySQL only
 pub fn timestamp_diff<T: Into<SelectField>>(date: T, unit: DateSubUnit) -> Int{
     Int::with_name(format!("TIMESTAMPDIFF ({}, {}, CURDATE())", unit, date.into().to_string()))
 }
 
 pub fn curdate() -> Varchar{
-    Varchar::with_name("CURDATE()".to_string())
+    Varchar::with_name(MySqlDialect.function_call("CURDATE", &[]))
 }
 
 pub fn year<T: Into<SelectField>>(field:T) -> Varchar{
-    Varchar::with_name(format!("YEAR({})", field.into().to_string()))
+    Varchar::with_name(MySqlDialect.function_call("YEAR", &[field.into().to_string()]))
 }
 
 pub fn extract_year<T: Into<SelectField>>(field:T) -> Varchar{
@@ -108,33 +184,69 @@ pub fn extract_year<T: Into<SelectField>>(field:T) -> Varchar{
 }
 
 pub fn year_diff<T: Into<SelectField>>(field:T, another_year:i32) -> Int{
-    Int::with_name(format!("ABS({} - YEAR({}))", another_year, field.into().to_string()))
+    Int::with_name(format!("ABS({} - {})", another_year, MySqlDialect.function_call("YEAR", &[field.into().to_string()])))
 }
 
 pub fn date<T: Into<SelectField>>(field:T) -> Varchar{
-    Varchar::with_name(format!("DATE({})", field.into().to_string()))
+    Varchar::with_name(MySqlDialect.function_call("DATE", &[field.into().to_string()]))
 }
 
 pub fn month<T: Into<SelectField>>(field:T) -> Int{
-    Int::with_name(format!("MONTH({})", field.into().to_string()))
+    Int::with_name(MySqlDialect.function_call("MONTH", &[field.into().to_string()]))
 }
 
-///DATE_SUB(date, INTERVAL value unit)
+///DATE_SUB(date, INTERVAL value unit), MySQL only
 pub fn date_sub<T: Into<SelectField>>(value: i32, unit: DateSubUnit) -> Date{
     Date::with_name(format!("DATE_SUB (CURDATE(), INTERVAL {} {})", value, unit))
 }
 
 pub fn group_concat<T: Into<SelectField>>(fields: Vec<T>) -> Varchar{
-    let fields_str = fields.into_iter().map(|field| field.into().to_string()).collect::<Vec<String>>().join(",");
-    Varchar::with_name(format!("group_concat({})",fields_str))
+    let fields = fields.into_iter().map(|field| field.into().to_string()).collect::<Vec<String>>();
+    Varchar::with_name(MySqlDialect.function_call("group_concat", &fields))
 }
 
 pub fn concat<T: Into<SelectField>>(fields: Vec<T>) -> Varchar{
-    let fields_str = fields.into_iter().map(|field| field.into().to_string()).collect::<Vec<String>>().join(",");
-    Varchar::with_name(format!("concat({})",fields_str))
+    let fields = fields.into_iter().map(|field| field.into().to_string()).collect::<Vec<String>>();
+    Varchar::with_name(MySqlDialect.function_call("concat", &fields))
 }
 
-pub async fn insert_or_update<A,T: Serialize + for<'de> serde::Deserialize<'de>>(table_with_value: &mut A) -> Result<T,Error> where A : Table{
+///inserts rows with multi-row INSERT statements instead of one statement per row, returns the affected rows
+pub async fn insert_many<A>(rows: &[A]) -> Result<u64,Error> where A : Table{
+    let mut conn = default_pool()?.acquire().await?;
+    on_default_pool(insert_many_with(&mut conn, rows, false)).await
+}
+
+///insert_many, rows with an existing key are updated by the upsert clause of the dialect (ON DUPLICATE KEY UPDATE on MySQL)
+pub async fn upsert_many<A>(rows: &[A]) -> Result<u64,Error> where A : Table{
+    let mut conn = default_pool()?.acquire().await?;
+    on_default_pool(insert_many_with(&mut conn, rows, true)).await
+}
+
+///insert_many on the given connection, the rows are sent in chunks under the server's max_allowed_packet.
+///chunks are committed one by one, pass `&mut **tx` to insert all rows or none
+pub async fn insert_many_with<A>(conn: &mut MySqlConnection, rows: &[A], update_on_duplicate: bool) -> Result<u64,Error> where A : Table{
+    if rows.is_empty() {
+        return Ok(0);
+    }
+    let max_allowed_packet: i64 = sqlx::query_scalar("SELECT @@max_allowed_packet").fetch_one(&mut *conn).await?;
+    let statements = build_insert_many(rows, update_on_duplicate, max_allowed_packet as usize)
+        .map_err(|e| Error::Configuration(e.message.into()))?;
+    let mut rows_affected = 0;
+    for (query_string, args) in statements {
+        let query = sqlx::query_with(&query_string, to_mysql_arguments(&args)?).execute(&mut *conn);
+        let operation = if update_on_duplicate { Operation::Insert_Or_Update } else { Operation::Insert };
+        rows_affected += intercept(&query_string, &args, &operation, &rows[0].name(), MySqlQueryResult::rows_affected, query).await?.rows_affected();
+    }
+    Ok(rows_affected)
+}
+
+pub async fn insert_or_update<A,T: Serialize + for<'de> serde::Deserialize<'de> + 'static>(table_with_value: &mut A) -> Result<T,Error> where A : Table{
+    let mut conn = default_pool()?.acquire().await?;
+    on_default_pool(insert_or_update_with(&mut conn, table_with_value)).await
+}
+
+///insert_or_update on the given connection, pass `&mut **tx.lock().await` to run it inside a transaction()
+pub async fn insert_or_update_with<A,T: Serialize + for<'de> serde::Deserialize<'de> + 'static>(conn: &mut MySqlConnection, table_with_value: &mut A) -> Result<T,Error> where A : Table{
     let target_table:TargetTable = TargetTable::new(table_with_value);
     //let mut multiple_typed_primary_key = MultiTypedPrimaryKey{uuid_key:None,i32_key:None,i64_key:None,u64_key:None};
     let primary_key_vec = table_with_value.primary_key();
@@ -167,36 +279,36 @@ pub async fn insert_or_update<A,T: Serialize + for<'de> serde::Deserialize<'de>>
     }
 
     //
-    let upsert_result = QueryBuilder::upsert_table_with_value(table_with_value).execute().await;
+    let upsert_result = QueryBuilder::upsert_table_with_value(table_with_value).execute_with(&mut *conn).await;
     match upsert_result {
         Ok(query_result) => {
-            let mut condition= Condition::new("1 = 1".to_string());
+            let mut condition= Condition::And(vec![]);
             if query_result.rows_affected() > 0 {
                 if table_with_value.primary_key().len()>1{
                     let mut primary_key_as_conditions = vec![];
-                    construct_upsert_primary_key_value(&table_with_value.primary_key(), &mut vec![], &mut vec![], &mut primary_key_as_conditions);
+                    construct_upsert_primary_key_value(&table_with_value.primary_key(), &mut vec![], &mut vec![], &mut vec![], &mut primary_key_as_conditions);
                     for cond in primary_key_as_conditions {
-                        condition = condition.and(Condition::new(cond))
+                        condition = condition.and(cond)
                     }
                 }else{
                     if(text_primary_key_value.is_some()){
-                        condition = condition.and(Condition::new(format!("{} = '{}'",&text_primary_key_name.unwrap(),&text_primary_key_value.unwrap_or_default())))
+                        condition = condition.and(Condition::compare(text_primary_key_name.unwrap(), "=", FieldValue::String(text_primary_key_value.unwrap_or_default())))
                     }else{ //primary key is not a string
                         let primary_key = primary_key_vec.get(0).unwrap().clone();
                         let primary_key_name = primary_key.get_col_name();
                         let last_insert_id = query_result.last_insert_id();
                         if last_insert_id>0 {
-                            condition = condition.and(Condition::new(format!("{} = {}",primary_key_name, last_insert_id)));
+                            condition = condition.and(Condition::compare(primary_key_name, "=", FieldValue::U64(last_insert_id)));
                         }else{ //updated a row?
                             let mut primary_key_as_conditions = vec![];
-                            construct_upsert_primary_key_value(&table_with_value.primary_key(), &mut vec![], &mut vec![], &mut primary_key_as_conditions);
+                            construct_upsert_primary_key_value(&table_with_value.primary_key(), &mut vec![], &mut vec![], &mut vec![], &mut primary_key_as_conditions);
                             for cond in primary_key_as_conditions {
-                                condition = condition.and(Condition::new(cond))
+                                condition = condition.and(cond)
                             }
                         }
                     }
                 }
-                let result = QueryBuilder::select_all_fields().from(table_with_value).where_(condition).fetch_one().await;
+                let result = QueryBuilder::select_all_fields().from(table_with_value).where_(condition).fetch_one_with(&mut *conn).await;
                 match result {
                     Ok(row) => {
                         match row {
@@ -234,10 +346,15 @@ pub fn delete_one_where<A>(table:& A, condition: Condition) -> QueryBuilder wher
     QueryBuilder::delete_one_where(table, condition)
 }
 
-/*pub fn insert_into<'a,A>(table:&'a A) -> QueryBuilder<'a> where A : Table{
+pub fn delete_all_where<A>(table:& A, condition: Condition) -> QueryBuilder where A : Table{
+    QueryBuilder::delete_all_where(table, condition)
+}
 
+///INSERT INTO table (columns) SELECT ..., e.g. `insert_into(&archive).columns(vec![..]).select(select(vec![..]).from(&orders))`
+pub fn insert_into<A>(table:& A) -> QueryBuilder where A : Table{
+    QueryBuilder::insert_into(table)
 }
 
-pub fn update<'a,A>(table:&'a A) -> QueryBuilder<'a> where A : Table{
+/*pub fn update<'a,A>(table:&'a A) -> QueryBuilder<'a> where A : Table{
 
 }*/
\ No newline at end of file
@@ -0,0 +1,161 @@
+use crate::configuration::{set_encryptor, Encryptor};
+use crate::query::builder::Field;
+use crate::mapping::column_types::{Char, Date, Enum, Int, Json, Varchar};
+use crate::mapping::description::{SqlColumn, Table};
+
+///item(id, name, order), `order` is a keyword so every statement on it needs quoting
+#[derive(Clone, Debug)]
+pub(crate) struct Item {
+    pub id: Int,
+    pub name: Varchar,
+    pub order: Varchar,
+    pub alias: Option<String>,
+}
+
+impl Item {
+    ///the columns qualified with the table, for selects and conditions
+    pub fn columns() -> Item {
+        Item { id: Int::with_qualified_name("item".into(), "id".into()), name: Varchar::with_qualified_name("item".into(), "name".into()), order: Varchar::with_qualified_name("item".into(), "order".into()), alias: None }
+    }
+
+    ///`item as alias`, the columns are qualified with the alias
+    pub fn aliased(alias: &str) -> Item {
+        Item { id: Int::with_qualified_name(alias.into(), "id".into()), name: Varchar::with_qualified_name(alias.into(), "name".into()), order: Varchar::with_qualified_name(alias.into(), "order".into()), alias: Some(alias.to_string()) }
+    }
+
+    ///a row to insert or update
+    pub fn row(id: i32, name: &str, order: &str) -> Item {
+        Item { id: Int::with_name_value("id".into(), Some(id)), name: Varchar::with_name_value("name".into(), Some(name.into())), order: Varchar::with_name_value("order".into(), Some(order.into())), alias: None }
+    }
+}
+
+impl Table for Item {
+    fn name(&self) -> String {
+        match &self.alias {
+            Some(alias) => format!("item as {}", alias),
+            None => "item".to_string(),
+        }
+    }
+
+    fn all_columns(&self) -> Vec<SqlColumn> {
+        vec![SqlColumn::Int(Some(self.id.clone())), SqlColumn::Varchar(Some(self.name.clone())), SqlColumn::Varchar(Some(self.order.clone()))]
+    }
+
+    fn primary_key(&self) -> Vec<SqlColumn> {
+        vec![SqlColumn::Int(Some(self.id.clone()))]
+    }
+
+    fn update_primary_key(&mut self, _primary_key: Vec<SqlColumn>) {}
+}
+
+///account(id, secret), secret is encrypted
+#[derive(Clone, Debug)]
+pub(crate) struct Account {
+    pub id: Int,
+    pub secret: Varchar,
+}
+
+impl Account {
+    pub fn row(id: i32, secret: &str) -> Account {
+        Account { id: Int::with_name_value("id".into(), Some(id)), secret: Varchar::with_name_value("secret".into(), Some(secret.into())).set_encrypted(true) }
+    }
+}
+
+impl Table for Account {
+    fn name(&self) -> String {
+        "account".to_string()
+    }
+
+    fn all_columns(&self) -> Vec<SqlColumn> {
+        vec![SqlColumn::Int(Some(self.id.clone())), SqlColumn::Varchar(Some(self.secret.clone()))]
+    }
+
+    fn primary_key(&self) -> Vec<SqlColumn> {
+        vec![SqlColumn::Int(Some(self.id.clone()))]
+    }
+
+    fn update_primary_key(&mut self, _primary_key: Vec<SqlColumn>) {}
+}
+
+///event(id, day, status, payload), status is an enum column
+#[derive(Clone, Debug)]
+pub(crate) struct Event {
+    pub id: Int,
+    pub day: Date,
+    pub status: Enum<String>,
+    pub payload: Json,
+}
+
+impl Event {
+    pub fn row(id: i32, day: chrono::NaiveDate, status: &str, payload: &str) -> Event {
+        Event { id: Int::with_name_value("id".into(), Some(id)), day: Date::with_name_value("day".into(), Some(day)), status: Enum::with_name_value("status".into(), Some(status.to_string())), payload: Json::with_name_value("payload".into(), Some(payload.into())) }
+    }
+}
+
+impl Table for Event {
+    fn name(&self) -> String {
+        "event".to_string()
+    }
+
+    //enum columns are listed as Varchar, the way generated tables list them
+    fn all_columns(&self) -> Vec<SqlColumn> {
+        vec![SqlColumn::Int(Some(self.id.clone())), SqlColumn::Date(Some(self.day.clone())), SqlColumn::Varchar(Some(self.status.clone().into())), SqlColumn::Json(Some(self.payload.clone()))]
+    }
+
+    fn primary_key(&self) -> Vec<SqlColumn> {
+        vec![SqlColumn::Int(Some(self.id.clone()))]
+    }
+
+    fn update_primary_key(&mut self, _primary_key: Vec<SqlColumn>) {}
+}
+
+///device(id, name), id is a uuid column of PostgreSQL
+#[derive(Clone, Debug)]
+pub(crate) struct Device {
+    pub id: Char,
+    pub name: Varchar,
+}
+
+impl Device {
+    pub fn row(id: &str, name: &str) -> Device {
+        Device { id: Char::with_name_value("id".into(), Some(id.into())).set_uuid(true), name: Varchar::with_name_value("name".into(), Some(name.into())) }
+    }
+}
+
+impl Table for Device {
+    fn name(&self) -> String {
+        "device".to_string()
+    }
+
+    fn all_columns(&self) -> Vec<SqlColumn> {
+        vec![SqlColumn::Char(Some(self.id.clone())), SqlColumn::Varchar(Some(self.name.clone()))]
+    }
+
+    fn primary_key(&self) -> Vec<SqlColumn> {
+        vec![SqlColumn::Char(Some(self.id.clone()))]
+    }
+
+    fn update_primary_key(&mut self, _primary_key: Vec<SqlColumn>) {}
+}
+
+//relies on the default encrypt_bound, which turns encrypt() into AES_ENCRYPT(?, 'key')
+struct TestEncryptor;
+
+impl Encryptor for TestEncryptor {
+    fn encrypt(&self, value: String) -> String {
+        format!("AES_ENCRYPT('{}', 'key')", value)
+    }
+
+    fn decrypt(&self, value: String) -> String {
+        format!("AES_DECRYPT({}, 'key')", value)
+    }
+
+    fn decrypt_field(&self, field: Field) -> String {
+        format!("AES_DECRYPT({}, 'key')", field.name)
+    }
+}
+
+///the encryptor is a process wide OnceCell, every test installs the same one
+pub(crate) fn use_test_encryptor() {
+    set_encryptor(TestEncryptor);
+}
@@ -0,0 +1,191 @@
+use std::any::Any;
+use std::future::Future;
+use std::sync::Arc;
+use sqlx::{Database, Error, Executor, Pool, Transaction};
+use sqlx_mysql::MySql;
+use tokio::sync::{Mutex, MutexGuard};
+use crate::query::pool::default_pool;
+
+tokio::task_local! {
+    //the TransactionHandle of the running transaction callback, a transaction started inside it on the same pool nests in that one
+    static CURRENT_TRANSACTION: Arc<dyn Any + Send + Sync>;
+}
+
+///the transaction of a transaction() callback, pass it to execute_in/fetch_in.
+///clones share the same transaction, depth is 0 for the transaction itself and n for its n-th nested SAVEPOINT
+pub struct TransactionHandle<DB: Database = MySql> {
+    transaction: Arc<Mutex<Transaction<'static, DB>>>,
+    depth: usize,
+    //pool_identity of the pool the transaction was started on
+    pool: usize,
+}
+
+impl<DB: Database> Clone for TransactionHandle<DB> {
+    fn clone(&self) -> Self {
+        TransactionHandle { transaction: self.transaction.clone(), depth: self.depth, pool: self.pool }
+    }
+}
+
+impl<DB: Database> TransactionHandle<DB> where for<'c> &'c mut DB::Connection: Executor<'c, Database = DB> {
+    ///the underlying transaction, e.g. `&mut **tx.lock().await` as the executor of a sqlx query.
+    ///statements of the same transaction run one at a time, don't hold the guard across another statement of it
+    pub async fn lock(&self) -> MutexGuard<'_, Transaction<'static, DB>> {
+        self.transaction.lock().await
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn savepoint(&self) -> String {
+        format!("rustnq_savepoint_{}", self.depth)
+    }
+
+    async fn run(&self, sql: String) -> Result<(), Error> {
+        let mut tx = self.lock().await;
+        (&mut **tx).execute(sql.as_str()).await.map(|_| ())
+    }
+}
+
+///runs callback in a transaction on the global pool, commits when it returns Ok and rolls back when it returns Err.
+///the callback gets the transaction to pass to execute_in/fetch_in, e.g. `transaction(|tx| async move { ... })`.
+///called inside the callback of another transaction on the global pool it nests in that transaction as a SAVEPOINT, which is released
+///when callback returns Ok and rolled back to when it returns Err, the outer transaction stays open either way
+pub async fn transaction<F, Fut, R>(callback: F) -> Result<R, Error>
+where
+    F: FnOnce(TransactionHandle) -> Fut,
+    Fut: Future<Output = Result<R, Error>>,
+{
+    transaction_with(default_pool()?, callback).await
+}
+
+///same as transaction, but on the given pool, e.g. a SQLite pool or the pool of a second database.
+///it nests only in a transaction started on the same pool, inside the callback of a transaction on another pool it starts its own
+pub async fn transaction_with<DB, F, Fut, R>(pool: &Pool<DB>, callback: F) -> Result<R, Error>
+where
+    DB: Database,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    F: FnOnce(TransactionHandle<DB>) -> Fut,
+    Fut: Future<Output = Result<R, Error>>,
+{
+    if let Some(outer) = current_transaction::<DB>().filter(|outer| outer.pool == pool_identity(pool)) {
+        return nested_transaction(outer, callback).await;
+    }
+    let handle = TransactionHandle { transaction: Arc::new(Mutex::new(pool.begin().await?)), depth: 0, pool: pool_identity(pool) };
+    let result = CURRENT_TRANSACTION.scope(Arc::new(handle.clone()), callback(handle.clone())).await;
+    //a clone of the handle kept beyond the callback, e.g. moved into a spawned task, would still hold the connection
+    let tx = Arc::try_unwrap(handle.transaction)
+        .map_err(|_| Error::Protocol("the transaction is still used after its callback returned".to_string()))?
+        .into_inner();
+    match result {
+        Ok(value) => {
+            tx.commit().await?;
+            Ok(value)
+        }
+        Err(error) => {
+            tx.rollback().await?;
+            Err(error)
+        }
+    }
+}
+
+//clones of a pool share its connect options, so their address tells pools apart
+fn pool_identity<DB: Database>(pool: &Pool<DB>) -> usize {
+    Arc::as_ptr(&pool.connect_options()) as *const () as usize
+}
+
+fn current_transaction<DB: Database>() -> Option<TransactionHandle<DB>> {
+    CURRENT_TRANSACTION.try_with(|current| current.downcast_ref::<TransactionHandle<DB>>().cloned()).ok().flatten()
+}
+
+async fn nested_transaction<DB, F, Fut, R>(outer: TransactionHandle<DB>, callback: F) -> Result<R, Error>
+where
+    DB: Database,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    F: FnOnce(TransactionHandle<DB>) -> Fut,
+    Fut: Future<Output = Result<R, Error>>,
+{
+    let handle = TransactionHandle { transaction: outer.transaction.clone(), depth: outer.depth + 1, pool: outer.pool };
+    handle.run(format!("SAVEPOINT {}", handle.savepoint())).await?;
+    let result = CURRENT_TRANSACTION.scope(Arc::new(handle.clone()), callback(handle.clone())).await;
+    match result {
+        Ok(value) => {
+            handle.run(format!("RELEASE SAVEPOINT {}", handle.savepoint())).await?;
+            Ok(value)
+        }
+        Err(error) => {
+            handle.run(format!("ROLLBACK TO SAVEPOINT {}", handle.savepoint())).await?;
+            Err(error)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use sqlx::Row;
+    use sqlx_sqlite::{Sqlite, SqlitePool, SqlitePoolOptions};
+
+    async fn pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        sqlx::query("create table item (id integer primary key)").execute(&pool).await.unwrap();
+        pool
+    }
+
+    async fn insert(tx: &TransactionHandle<Sqlite>, id: i32) -> Result<(), Error> {
+        sqlx::query("insert into item (id) values (?)").bind(id).execute(&mut **tx.lock().await).await.map(|_| ())
+    }
+
+    async fn ids(pool: &SqlitePool) -> Vec<i64> {
+        sqlx::query("select id from item order by id").fetch_all(pool).await.unwrap().iter().map(|row| row.get(0)).collect()
+    }
+
+    #[tokio::test]
+    async fn nested_transactions_roll_back_to_their_savepoint() {
+        let pool = pool().await;
+        let inner_pool = pool.clone();
+        transaction_with(&pool, |tx| async move {
+            insert(&tx, 1).await?;
+            let failed = transaction_with(&inner_pool, |nested| async move {
+                assert_eq!(nested.depth(), 1);
+                insert(&nested, 2).await?;
+                Err::<(), _>(Error::RowNotFound)
+            }).await;
+            assert!(failed.is_err());
+            transaction_with(&inner_pool, |nested| async move { insert(&nested, 3).await }).await?;
+            insert(&tx, 4).await
+        }).await.unwrap();
+        assert_eq!(ids(&pool).await, vec![1, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn failing_transaction_rolls_back_its_nested_ones() {
+        let pool = pool().await;
+        let inner_pool = pool.clone();
+        let result = transaction_with(&pool, |tx| async move {
+            insert(&tx, 1).await?;
+            transaction_with(&inner_pool, |nested| async move { insert(&nested, 2).await }).await?;
+            insert(&tx, 1).await
+        }).await;
+        assert!(result.is_err());
+        assert!(ids(&pool).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_transaction_on_another_pool_does_not_nest() {
+        let other_pool = pool().await;
+        let pool = pool().await;
+        let inner_pool = other_pool.clone();
+        let result = transaction_with(&pool, |tx| async move {
+            insert(&tx, 1).await?;
+            transaction_with(&inner_pool, |other| async move {
+                assert_eq!(other.depth(), 0);
+                insert(&other, 2).await
+            }).await?;
+            Err::<(), _>(Error::RowNotFound)
+        }).await;
+        assert!(result.is_err());
+        assert!(ids(&pool).await.is_empty());
+        assert_eq!(ids(&other_pool).await, vec![2]);
+    }
+}
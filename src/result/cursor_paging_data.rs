@@ -0,0 +1,23 @@
+use serde::{Serialize,Deserialize};
+
+///a page of fetch_after, pass next_cursor or prev_cursor back to fetch_after to get the page after or before it
+#[derive(Debug, Deserialize,Serialize)]
+pub struct CursorPagingData<T> {
+    pub data: Vec<T>,
+    pub page_size: i32,
+    ///None on the last page
+    pub next_cursor: Option<String>,
+    ///None on the first page
+    pub prev_cursor: Option<String>,
+}
+
+impl<T> CursorPagingData<T> {
+    pub fn new(data: Vec<T>, page_size: i32, next_cursor: Option<String>, prev_cursor: Option<String>) -> Self {
+        CursorPagingData {
+            data,
+            page_size,
+            next_cursor,
+            prev_cursor,
+        }
+    }
+}
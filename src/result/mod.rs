@@ -1,2 +1,4 @@
 pub mod paging_data;
-pub use paging_data::*;
\ No newline at end of file
+pub use paging_data::*;
+pub mod cursor_paging_data;
+pub use cursor_paging_data::*;
\ No newline at end of file